@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+
+use crate::{interpreter::Interpreter, runner::Backend, virtual_machine::{InterpretResult, InterpreterOutput, VirtualMachine}};
+
+//Wall times of one program over several runs
+pub struct BenchResult {
+    pub name: String,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+    //Bytecode instructions executed by one run, the tree-walker has none to count
+    pub instructions: Option<u64>
+}
+
+//Runs the source on a fresh interpreter each time with its printed output thrown away.
+//A run that doesn't complete stops the benchmark and returns its error lines
+pub fn bench(name: &str, source: &str, backend: Backend, iterations: usize) -> Result<BenchResult, Vec<String>> {
+    let mut times = vec![];
+    let mut instructions = None;
+    for _ in 0..iterations.max(1) {
+        let start = Instant::now();
+        let output = match backend {
+            Backend::Vm => {
+                let mut vm = VirtualMachine::new();
+                vm.set_output(Box::new(|_: &str| ()));
+                let output = vm.interpret_source(source.to_string(), false, false);
+                instructions = Some(vm.instruction_count());
+                output
+            },
+            Backend::Tree => {
+                let mut interpreter = Interpreter::new();
+                interpreter.set_output(Box::new(|_: &str| ()));
+                interpreter.interpret_source(source.to_string())
+            }
+        };
+        times.push(start.elapsed());
+        check(output)?;
+    }
+    times.sort();
+    return Ok(BenchResult {
+        name: name.to_string(),
+        min: times[0],
+        //The upper middle run when there's an even number of them
+        median: times[times.len() / 2],
+        max: times[times.len() - 1],
+        instructions
+    });
+}
+
+fn check(output: InterpreterOutput) -> Result<(), Vec<String>> {
+    match output.result {
+        InterpretResult::InterpretOk => Ok(()),
+        InterpretResult::InterpretExit(code) => Err(vec![format!("Exited with {}", code)]),
+        _ => Err(output.errors)
+    }
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+    return duration.as_secs_f64() * 1000.0;
+}
+
+//One line per benchmark under a header:
+//
+//benchmark            min (ms)  median (ms)     max (ms)   instructions
+//fib                     42.05        42.57        46.83        2913423
+pub fn format_table(results: &[BenchResult]) -> String {
+    let mut table = format!("{:<16} {:>12} {:>12} {:>12} {:>14}\n", "benchmark", "min (ms)", "median (ms)", "max (ms)", "instructions");
+    for result in results {
+        let instructions = result.instructions.map(|count| count.to_string()).unwrap_or(format!("-"));
+        table.push_str(&format!("{:<16} {:>12.2} {:>12.2} {:>12.2} {:>14}\n", result.name, milliseconds(result.min), milliseconds(result.median), milliseconds(result.max), instructions));
+    }
+    return table;
+}
+
+//For comparing runs across commits, instructions is null on the tree-walker
+pub fn format_json(results: &[BenchResult], backend: Backend, iterations: usize) -> String {
+    let backend = match backend {
+        Backend::Vm => "vm",
+        Backend::Tree => "tree"
+    };
+    let benchmarks: Vec<String> = results.iter().map(|result| {
+        let instructions = result.instructions.map(|count| count.to_string()).unwrap_or(format!("null"));
+        format!(
+            "    {{\"name\": \"{}\", \"min_ms\": {:.3}, \"median_ms\": {:.3}, \"max_ms\": {:.3}, \"instructions\": {}}}",
+            escape(&result.name), milliseconds(result.min), milliseconds(result.median), milliseconds(result.max), instructions
+        )
+    }).collect();
+    return format!("{{\n  \"backend\": \"{}\",\n  \"iterations\": {},\n  \"benchmarks\": [\n{}\n  ]\n}}\n", backend, iterations, benchmarks.join(",\n"));
+}
+
+fn escape(text: &str) -> String {
+    return text.replace('\\', "\\\\").replace('"', "\\\"");
+}
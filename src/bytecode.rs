@@ -1,9 +1,17 @@
-use core::fmt;
-use std::{fmt::Display, ops::{Add, Div, Mul, Neg, Sub}, vec};
-#[derive(PartialEq)]
+use std::{collections::HashMap, vec};
+
+use crate::heap::ObjRef;
+
+//Each instruction is one opcode byte followed by its operands, the comment on each opcode lists them.
+//Constant and name operands are indices into the chunk's constants
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum OpCode {
     Return,
-    Constant(usize),
+    //u8 constant
+    Constant,
+    //u24 constant, used once a chunk has more than 256 constants
+    ConstantLong,
     Negate,
     Add,
     Subtract,
@@ -15,59 +23,168 @@ pub enum OpCode {
     Less,
     Print,
     Pop,
-    DefineGlobal(usize, bool),
-    GetGlobal(String),
-    SetGlobal(String),
-    GetLocal(usize),
-    SetLocal(usize),
-    JumpIfFalse(usize),
-    Jump(usize),
-    Loop(usize),
-    SwitchJump(usize),
-    Break,
-    Continue
+    //u16 name, u8 is_const
+    DefineGlobal,
+    //u16 name
+    GetGlobal,
+    //u16 name
+    SetGlobal,
+    //u8 slot
+    GetLocal,
+    //u8 slot
+    SetLocal,
+    //u16 forward jump
+    JumpIfFalse,
+    //u16 forward jump
+    Jump,
+    //u16 backward jump
+    Loop,
+    //u16 forward jump taken when the case doesn't match
+    SwitchJump,
+    //u8 argument count
+    Call,
+    //u16 function, then u8 is_local and u8 index for each captured variable
+    Closure,
+    //u8 upvalue
+    GetUpvalue,
+    //u8 upvalue
+    SetUpvalue,
+    CloseUpvalue,
+    //u16 name
+    Class,
+    //u16 name
+    Method,
+    Inherit,
+    //u16 name
+    GetProperty,
+    //u16 name
+    SetProperty,
+    //u16 name, u8 argument count. Avoids creating a bound method for direct calls
+    Invoke,
+    //u16 name
+    GetSuper,
+    //u16 name, u8 argument count
+    SuperInvoke
+}
+
+//Same order as the declaration so an opcode's byte indexes its entry
+const OP_CODES: [OpCode; 36] = [
+    OpCode::Return, OpCode::Constant, OpCode::ConstantLong, OpCode::Negate, OpCode::Add, OpCode::Subtract,
+    OpCode::Multiply, OpCode::Divide, OpCode::Not, OpCode::Equal, OpCode::Greater, OpCode::Less, OpCode::Print,
+    OpCode::Pop, OpCode::DefineGlobal, OpCode::GetGlobal, OpCode::SetGlobal, OpCode::GetLocal, OpCode::SetLocal,
+    OpCode::JumpIfFalse, OpCode::Jump, OpCode::Loop, OpCode::SwitchJump, OpCode::Call, OpCode::Closure,
+    OpCode::GetUpvalue, OpCode::SetUpvalue, OpCode::CloseUpvalue, OpCode::Class, OpCode::Method, OpCode::Inherit,
+    OpCode::GetProperty, OpCode::SetProperty, OpCode::Invoke, OpCode::GetSuper, OpCode::SuperInvoke
+];
+
+impl OpCode {
+    pub fn from_byte(byte: u8) -> Option<OpCode> {
+        return OP_CODES.get(byte as usize).copied();
+    }
 }
 
 pub struct Chunk {
-    pub code: Vec<(OpCode, usize)>,
-    pub constant: Vec<Value>
+    pub code: Vec<u8>,
+    pub constant: Vec<Value>,
+    //Run-length encoded source lines as (line, number of bytes on that line)
+    pub lines: Vec<(usize, usize)>
 }
 
 impl Default for Chunk {
     fn default() -> Self {
         Chunk {
             code: vec![],
-            constant: vec![]
+            constant: vec![],
+            lines: vec![]
         }
     }
 }
 
 impl Chunk {
-    pub fn chunk_write(&mut self, op_code: OpCode, line: usize) {
-        self.code.push((op_code, line));
+    pub fn chunk_write(&mut self, byte: u8, line: usize) {
+        self.code.push(byte);
+        match self.lines.last_mut() {
+            Some((last_line, count)) if *last_line == line => *count += 1,
+            _ => self.lines.push((line, 1))
+        }
     }
 
     pub fn constant_write(&mut self, value: Value) -> usize {
         self.constant.push(value);
         return self.constant.len() - 1;
     }
+
+    //Source line of the byte at the given offset
+    pub fn line(&self, offset: usize) -> usize {
+        let mut start = 0;
+        for (line, count) in self.lines.iter() {
+            start += count;
+            if offset < start {
+                return *line;
+            }
+        }
+        return 0;
+    }
+
+    pub fn read_short(&self, offset: usize) -> usize {
+        return (self.code[offset] as usize) << 8 | self.code[offset + 1] as usize;
+    }
+
+    pub fn read_long(&self, offset: usize) -> usize {
+        return (self.code[offset] as usize) << 16 | (self.code[offset + 1] as usize) << 8 | self.code[offset + 2] as usize;
+    }
 }
 
-#[derive(Clone, PartialEq)]
+//Objects live in the heap and are only referenced by handle, so values are cheap to copy.
+//Strings are interned so comparing handles compares contents
+#[derive(Clone, Copy, PartialEq)]
 pub enum Value {
     Number(f64),
     Bool(bool),
-    String(String),
+    String(ObjRef),
+    Function(ObjRef),
+    Closure(ObjRef),
+    Class(ObjRef),
+    Instance(ObjRef),
+    BoundMethod(ObjRef),
+    Native(ObjRef),
     Nil
 }
 
-impl Display for Value {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl Value {
+    //Heap object this value refers to, if any
+    pub fn as_object(&self) -> Option<ObjRef> {
         match self {
-            Value::Number(n) => write!(f, "{}", n),
-            Value::Bool(b) => write!(f, "{}", b),
-            Value::String(s) => write!(f, "{}", s),
-            Value::Nil => write!(f, "nil")
+            Value::String(object) | Value::Function(object) | Value::Closure(object) | Value::Class(object)
+                | Value::Instance(object) | Value::BoundMethod(object) | Value::Native(object) => Some(*object),
+            Value::Number(_) | Value::Bool(_) | Value::Nil => None
         }
     }
 }
+
+//Method and field names are interned strings
+pub struct Class {
+    pub name: String,
+    pub methods: HashMap<ObjRef, ObjRef>
+}
+
+pub struct Instance {
+    pub class: ObjRef,
+    pub fields: HashMap<ObjRef, Value>
+}
+
+pub struct BoundMethod {
+    pub receiver: Value,
+    pub method: ObjRef
+}
+
+pub struct Closure {
+    pub function: ObjRef,
+    pub upvalues: Vec<ObjRef>
+}
+
+//An upvalue points at a stack slot until the variable goes out of scope, then it owns the value
+pub enum Upvalue {
+    Open(usize),
+    Closed(Value)
+}
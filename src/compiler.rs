@@ -1,86 +1,162 @@
 
-use std::fmt::format;
+use std::{fmt::format, rc::Rc};
 
-use crate::{bytecode::{Chunk, OpCode, Value}, debug::disassemble_chunk, scanner::{scan, Literal, Scanner, Token, TokenType}};
+use crate::{bytecode::{Chunk, OpCode, Value}, debug::disassemble_chunk, heap::{Heap, ObjRef, Object}, scanner::{scan, Literal, Scanner, Token, TokenType}, diagnostics::{Diagnostic, ErrorCode}};
 
-pub fn compile(src: String) -> CompilerOutput {
-    let mut compiler = compiler_initalize(src, FunctionType::Script);
+//Strings and functions created while compiling are allocated in the given heap
+pub fn compile(src: String, heap: &mut Heap) -> CompilerOutput {
+    return compile_script(src, heap, false);
+}
+
+//Compiles REPL input, a top level expression statement prints its value and may leave out the final ';'
+pub fn compile_repl(src: String, heap: &mut Heap) -> CompilerOutput {
+    return compile_script(src, heap, true);
+}
+
+fn compile_script(src: String, heap: &mut Heap, repl: bool) -> CompilerOutput {
+    let mut compiler = compiler_initalize(src, FunctionType::Script, heap);
     compiler.advance();
    
     while !compiler.token_match(TokenType::EOF) {
-        compiler.declaration();
+        if repl && compiler.at_expression_statement() {
+            compiler.repl_expression();
+        } else {
+            compiler.declaration();
+        }
     }
     
-    compiler.end_compiler();
+    let function = compiler.end_compiler();
     
-    if compiler.error_message.is_empty() {
-        return CompilerOutput::Success(compiler.function)
+    if compiler.diagnostics.is_empty() {
+        return CompilerOutput::Success(compiler.heap.alloc(Object::Function(function)))
     }  
-    return CompilerOutput::Error(compiler.error_message)
+    return CompilerOutput::Error(compiler.diagnostics)
 }
 
 pub struct Function {
-    arity: i32,
-    chunk: Chunk,
+    pub arity: usize,
+    //Shared so the VM can read the code while allocating into the heap
+    pub chunk: Rc<Chunk>,
     pub name: Option<String>,
+    pub upvalue_count: usize,
+    //Most locals alive at once, slot zero included. Local slots past it are never addressed
+    pub slot_count: usize,
 }
 
+#[derive(PartialEq)]
 enum FunctionType {
     Function,
+    Initializer,
+    Method,
     Script
 }
 
-fn new_function() -> Function {
+fn new_function(name: Option<String>) -> Function {
     Function {
         arity: 0,
-        name: None,
-        chunk: Chunk::default()
+        name,
+        chunk: Rc::new(Chunk::default()),
+        upvalue_count: 0,
+        slot_count: 1
     }
 }
 
 pub enum CompilerOutput {
-    Success(Function),
-    Error(Vec<String>)
+    Success(ObjRef),
+    Error(Vec<Diagnostic>)
 }
-struct Compiler {
+struct Compiler<'a> {
+    heap: &'a mut Heap,
     current: Token,
     previous: Token,
-    error_message: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
     panic_mode: bool,
     scanner: Scanner,
+    functions: Vec<FunctionCompiler>,
+    classes: Vec<ClassCompiler>
+}
+
+struct ClassCompiler {
+    has_superclass: bool
+}
+
+//State for the function currently being compiled, nested function declarations push a new one
+struct FunctionCompiler {
+    function: Function,
+    function_type: FunctionType,
     locals: Vec<Local>,
+    upvalues: Vec<Upvalue>,
     scope_depth: i32,
-    in_loop: bool,
-    function: Function,
-    function_type: FunctionType
+    loops: Vec<Loop>,
+}
+
+//Enclosing loop of the code being compiled, used to compile break and continue
+struct Loop {
+    //Where continue jumps back to, the increment clause for 'for' loops
+    start: usize,
+    scope_depth: i32,
+    break_jumps: Vec<usize>
 }
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Local {
     pub name: Token,
     pub depth: i32,
+    is_const: bool,
+    is_captured: bool
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Upvalue {
+    index: usize,
+    is_local: bool,
     is_const: bool
 }
 
 
-fn compiler_initalize(src: String, func_type: FunctionType) -> Compiler {
+fn compiler_initalize(src: String, func_type: FunctionType, heap: &mut Heap) -> Compiler<'_> {
     Compiler {
-        current: Token { token_type: TokenType::NIL, lexeme: format!(""), literal: None, line: 0 },
-        previous: Token { token_type: TokenType::NIL, lexeme: format!(""), literal: None, line: 0 },
-        error_message: vec![],
+        heap,
+        current: Token::synthetic(TokenType::NIL, "", 0),
+        previous: Token::synthetic(TokenType::NIL, "", 0),
+        diagnostics: vec![],
         panic_mode: false,
-        function_type: func_type,
-        function: new_function(),
         scanner: scan(src),
+        functions: vec![function_compiler_initalize(func_type, None)],
+        classes: vec![]
+    }
+}
+
+fn function_compiler_initalize(func_type: FunctionType, name: Option<String>) -> FunctionCompiler {
+    //Slot zero of every call frame holds the function being called, or the receiver for methods
+    let slot_zero_name = match func_type {
+        FunctionType::Method | FunctionType::Initializer => format!("this"),
+        _ => format!("")
+    };
+    let reserved_local = Local {
+        name: Token::synthetic(TokenType::IDENTIFIER, &slot_zero_name, 0),
+        depth: 0,
+        is_const: false,
+        is_captured: false
+    };
+    FunctionCompiler {
+        function: new_function(name),
+        function_type: func_type,
+        locals: vec![reserved_local],
+        upvalues: vec![],
         scope_depth: 0,
-        locals: Vec::new(),
-        in_loop: false,
+        loops: vec![]
     }
 }
 
-impl Compiler {
-    fn current_chunk(&mut self) -> &Chunk {
-       return &self.function.chunk;
+impl Compiler<'_> {
+    fn current_function(&mut self) -> &mut FunctionCompiler {
+        return self.functions.last_mut().unwrap();
+    }
+
+    fn current_chunk(&mut self) -> &mut Chunk {
+       //Only the compiler holds the chunk until the function is finished
+       return Rc::get_mut(&mut self.current_function().function.chunk).unwrap();
     }
 
     fn advance(&mut self) {
@@ -99,11 +175,29 @@ impl Compiler {
             return;
         }
 
-        self.parse_error(self.current.clone(),Some(message));
+        self.parse_error(self.current.clone(), ErrorCode::ExpectToken, message);
     }
 
     fn emit_byte(&mut self, op_code: OpCode) {
-        self.chunk_write(op_code, self.previous.line);
+        self.chunk_write(op_code as u8, self.previous.line);
+    }
+
+    //Operands are written straight after the opcode they belong to
+    fn emit_operand(&mut self, operand: u8) {
+        self.chunk_write(operand, self.previous.line);
+    }
+
+    fn emit_short(&mut self, operand: usize) {
+        self.emit_operand((operand >> 8) as u8);
+        self.emit_operand(operand as u8);
+    }
+
+    fn emit_with_constant(&mut self, op_code: OpCode, constant: usize) {
+        if constant > u16::MAX as usize {
+            self.parse_error(self.previous.clone(), ErrorCode::LimitExceeded, format!("Too many constants in one chunk."));
+        }
+        self.emit_byte(op_code);
+        self.emit_short(constant);
     }
 
     fn emit_bytes(&mut self, op_1: OpCode, op_2: OpCode) {
@@ -111,8 +205,8 @@ impl Compiler {
         self.emit_byte(op_2);
     }
 
-    fn chunk_write(&mut self, op_code: OpCode, line: usize) {
-        self.current_chunk().code.push((op_code, line));
+    fn chunk_write(&mut self, byte: u8, line: usize) {
+        self.current_chunk().chunk_write(byte, line);
     }
 
     fn constant_write(&mut self, value: Value) -> usize {
@@ -120,8 +214,20 @@ impl Compiler {
         return self.current_chunk().constant.len() - 1;
     }
 
-    fn end_compiler(&mut self) {
-        self.emit_byte(OpCode::Return)
+    fn end_compiler(&mut self) -> Function {
+        self.emit_return();
+        return self.functions.pop().unwrap().function;
+    }
+
+    fn emit_return(&mut self) {
+        //Initializers always return the instance being constructed
+        if self.current_function().function_type == FunctionType::Initializer {
+            self.emit_byte(OpCode::GetLocal);
+            self.emit_operand(0);
+        } else {
+            self.emit_constant(Value::Nil);
+        }
+        self.emit_byte(OpCode::Return);
     }
 
     fn expression(&mut self) {
@@ -129,14 +235,20 @@ impl Compiler {
     }
 
     fn declaration(&mut self) {
-        if self.token_match(TokenType::VAR) {
+        if self.token_match(TokenType::CLASS) {
+            self.declaration_class();
+
+        } else if self.token_match(TokenType::FUN) {
+            self.declaration_fun();
+
+        } else if self.token_match(TokenType::VAR) {
             self.declaration_var(false);
 
         } else if self.token_match(TokenType::CONST) {
             self.declaration_var(true);
 
         } else {
-            self.statement(self.in_loop);
+            self.statement();
         }
         if self.panic_mode {
             self.synchronize();
@@ -156,27 +268,117 @@ impl Compiler {
         self.define_variable(global, is_const);
     }
 
+    fn declaration_class(&mut self) {
+        let global = self.parse_variable(format!("Expect class name."), false);
+        let class_name = self.previous.clone();
+        let name_constant = self.identifier_constant(&class_name);
+
+        self.emit_with_constant(OpCode::Class, name_constant);
+        self.define_variable(global, false);
+        self.classes.push(ClassCompiler { has_superclass: false });
+
+        if self.token_match(TokenType::LESS) {
+            self.consume(TokenType::IDENTIFIER, format!("Expect superclass name."));
+            variable(self, false);
+
+            if class_name.lexeme == self.previous.lexeme {
+                self.parse_error(self.previous.clone(), ErrorCode::InheritFromSelf, format!("A class can't inherit from itself."));
+            }
+
+            //The superclass is stored in a local named super so methods can capture it
+            self.begin_scope();
+            self.add_local(synthetic_token("super"), false);
+            self.define_variable(0, false);
+
+            self.named_variable(&class_name, false);
+            self.emit_byte(OpCode::Inherit);
+            self.classes.last_mut().unwrap().has_superclass = true;
+        }
+
+        self.named_variable(&class_name, false);
+        self.consume(TokenType::LEFT_BRACE, format!("Expect '{{' before class body."));
+        while self.current.token_type != TokenType::RIGHT_BRACE && self.current.token_type != TokenType::EOF {
+            self.method();
+        }
+        self.consume(TokenType::RIGHT_BRACE, format!("Expect '}}' after class body."));
+        self.emit_byte(OpCode::Pop);
+
+        if self.classes.pop().unwrap().has_superclass {
+            self.end_scope();
+        }
+    }
+
+    fn method(&mut self) {
+        self.consume(TokenType::IDENTIFIER, format!("Expect method name."));
+        let name = self.previous.lexeme.clone();
+        let name_constant = self.identifier_constant(&self.previous.clone());
+        let function_type = if name == "init" { FunctionType::Initializer } else { FunctionType::Method };
+        self.function(function_type);
+        self.emit_with_constant(OpCode::Method, name_constant);
+    }
+
+    fn declaration_fun(&mut self) {
+        let global = self.parse_variable(format!("Expect function name."), false);
+        //A function can refer to itself in its body so it is initalized straight away
+        self.mark_initalized();
+        self.function(FunctionType::Function);
+        self.define_variable(global, false);
+    }
+
+    fn function(&mut self, function_type: FunctionType) {
+        let name = self.previous.lexeme.clone();
+        self.functions.push(function_compiler_initalize(function_type, Some(name)));
+        self.current_function().scope_depth += 1;
+
+        self.consume(TokenType::LEFT_PAREN, format!("Expect '(' after function name."));
+        if self.current.token_type != TokenType::RIGHT_PAREN {
+            loop {
+                self.current_function().function.arity += 1;
+                if self.current_function().function.arity > 255 {
+                    self.parse_error(self.current.clone(), ErrorCode::LimitExceeded, format!("Can't have more than 255 parameters."));
+                }
+                let constant = self.parse_variable(format!("Expect parameter name."), false);
+                self.define_variable(constant, false);
+                if !self.token_match(TokenType::COMMA) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RIGHT_PAREN, format!("Expect ')' after parameters."));
+        self.consume(TokenType::LEFT_BRACE, format!("Expect '{{' before function body."));
+        self.statement_block();
+
+        let upvalues = self.current_function().upvalues.clone();
+        let function = self.end_compiler();
+        let function = self.heap.alloc(Object::Function(function));
+        let constant = self.constant_write(Value::Function(function));
+        self.emit_with_constant(OpCode::Closure, constant);
+        for upvalue in upvalues {
+            self.emit_operand(upvalue.is_local as u8);
+            self.emit_operand(upvalue.index as u8);
+        }
+    }
+
     fn parse_variable(&mut self, error_message: String, is_const: bool) -> usize {
         self.consume(TokenType::IDENTIFIER, error_message);
 
         self.declare_variable(is_const);
         
-        if self.scope_depth > 0 {
+        if self.current_function().scope_depth > 0 {
             return 0
         }
 
-        let identifier_constant = self.identifier_constant(&self.previous.clone());
-        self.current_chunk().constant.push(Value::String(identifier_constant));
-        return self.current_chunk().constant.len() - 1;
+        return self.identifier_constant(&self.previous.clone());
 
     }
 
-    fn identifier_constant(&mut self, token: &Token) -> String {
-        return token.lexeme.clone();
+    fn identifier_constant(&mut self, token: &Token) -> usize {
+        let identifier = self.heap.intern(token.lexeme.clone());
+        return self.constant_write(Value::String(identifier));
     }
 
     fn declare_variable(&mut self, is_const: bool) {
-        if self.scope_depth == 0 {
+        if self.current_function().scope_depth == 0 {
             return;
         }
 
@@ -185,33 +387,48 @@ impl Compiler {
     }
 
     fn add_local(&mut self, token: Token, is_const: bool) {
-        let local =  Local {name: token.clone(), depth: -1, is_const};
-        for existing_locals in self.locals.clone() {
-            if existing_locals.name.lexeme == token.lexeme && self.scope_depth == existing_locals.depth {
-                self.parse_error(self.previous.clone(), Some(format!("Already a variable with this name in this scope.")));
+        //Local slots are addressed with a single byte
+        if self.current_function().locals.len() == 256 {
+            self.parse_error(self.previous.clone(), ErrorCode::LimitExceeded, format!("Too many local variables in function."));
+            return;
+        }
+        let local =  Local {name: token.clone(), depth: -1, is_const, is_captured: false};
+        for existing_locals in self.current_function().locals.clone() {
+            if existing_locals.name.lexeme == token.lexeme && self.current_function().scope_depth == existing_locals.depth {
+                let diagnostic = Diagnostic::at(&self.previous, ErrorCode::DuplicateVariable, format!("Already a variable with this name in this scope."));
+                self.report(diagnostic.with_note(format!("'{}' was first declared here", token.lexeme), Some(&existing_locals.name)));
             }
         }
-        self.locals.push(local);
+        self.current_function().locals.push(local);
+        let local_count = self.current_function().locals.len();
+        let function = &mut self.current_function().function;
+        function.slot_count = function.slot_count.max(local_count);
     }
 
     fn define_variable(&mut self, global: usize, is_const: bool) {
-        if self.scope_depth > 0 {
+        if self.current_function().scope_depth > 0 {
             self.mark_initalized();
             return;
         }
-        self.emit_byte(OpCode::DefineGlobal(global, is_const))
+        self.emit_with_constant(OpCode::DefineGlobal, global);
+        self.emit_operand(is_const as u8);
     }
 
     fn mark_initalized(&mut self) {
-        let index = self.locals.len() - 1;
-        self.locals[index].depth = self.scope_depth;
+        if self.current_function().scope_depth == 0 {
+            return;
+        }
+        let index = self.current_function().locals.len() - 1;
+        self.current_function().locals[index].depth = self.current_function().scope_depth;
     }
 
-    fn statement(&mut self, in_loop: bool) {
+    fn statement(&mut self) {
         if self.token_match(TokenType::PRINT) {
             self.statement_print();
         } else if self.token_match(TokenType::IF) {
             self.statement_if();
+        } else if self.token_match(TokenType::RETURN) {
+            self.statement_return();
         } else if self.token_match(TokenType::SWITCH) {
             self.statement_switch();
         } else if self.token_match(TokenType::WHILE) {
@@ -219,25 +436,83 @@ impl Compiler {
         } else if self.token_match(TokenType::FOR) {
             self.statement_for();
         } else if self.token_match(TokenType::LEFT_BRACE) {
-            self.begin_scope(in_loop);
+            self.begin_scope();
             self.statement_block();
             self.end_scope();
         } else if self.token_match(TokenType::BREAK) {
-            if !self.in_loop {
-                self.parse_error(self.previous.clone(), Some(format!("Break statements only allowed in 'for' or 'while' loops.")));
-            } else {
-                self.consume(TokenType::SEMICOLON, format!("Expect ';' after 'break'."));
-                self.emit_byte(OpCode::Break);
-            }
+            self.statement_break();
         } else if self.token_match(TokenType::CONTINUE) {
-            if !self.in_loop {
-                self.parse_error(self.previous.clone(), Some(format!("Continue statements only allowed in 'for' or 'while' loops.")));
+            self.statement_continue();
+        } else {
+            self.statement_expression()
+        }
+    }
+
+    fn statement_break(&mut self) {
+        if self.current_function().loops.is_empty() {
+            self.parse_error(self.previous.clone(), ErrorCode::LoopControlOutsideLoop, format!("Break statements only allowed in 'for' or 'while' loops."));
+            return;
+        }
+        self.consume(TokenType::SEMICOLON, format!("Expect ';' after 'break'."));
+
+        let loop_depth = self.current_function().loops.last().unwrap().scope_depth;
+        self.discard_locals(loop_depth);
+        let break_jump = self.emit_jump(OpCode::Jump);
+        self.current_function().loops.last_mut().unwrap().break_jumps.push(break_jump);
+    }
+
+    fn statement_continue(&mut self) {
+        if self.current_function().loops.is_empty() {
+            self.parse_error(self.previous.clone(), ErrorCode::LoopControlOutsideLoop, format!("Continue statements only allowed in 'for' or 'while' loops."));
+            return;
+        }
+        self.consume(TokenType::SEMICOLON, format!("Expect ';' after 'continue'."));
+
+        let loop_start = self.current_function().loops.last().unwrap().start;
+        let loop_depth = self.current_function().loops.last().unwrap().scope_depth;
+        self.discard_locals(loop_depth);
+        self.emit_loop(loop_start);
+    }
+
+    //Emits pops for locals declared deeper than depth without forgetting them, used when jumping out of scopes
+    fn discard_locals(&mut self, depth: i32) {
+        let mut local_count = self.current_function().locals.len();
+        while local_count > 0 && self.current_function().locals[local_count - 1].depth > depth {
+            if self.current_function().locals[local_count - 1].is_captured {
+                self.emit_byte(OpCode::CloseUpvalue);
             } else {
-                self.consume(TokenType::SEMICOLON, format!("Expect ';' after 'continue'."));
-                self.emit_byte(OpCode::Continue);
+                self.emit_byte(OpCode::Pop);
             }
+            local_count -= 1;
+        }
+    }
+
+    fn begin_loop(&mut self, start: usize) {
+        let scope_depth = self.current_function().scope_depth;
+        self.current_function().loops.push(Loop { start, scope_depth, break_jumps: vec![] });
+    }
+
+    fn end_loop(&mut self) {
+        let finished_loop = self.current_function().loops.pop().unwrap();
+        for break_jump in finished_loop.break_jumps {
+            self.patch_jump(break_jump);
+        }
+    }
+
+    fn statement_return(&mut self) {
+        if self.current_function().function_type == FunctionType::Script {
+            self.parse_error(self.previous.clone(), ErrorCode::ReturnFromTopLevel, format!("Can't return from top-level code."));
+        }
+
+        if self.token_match(TokenType::SEMICOLON) {
+            self.emit_return();
         } else {
-            self.statement_expression()
+            if self.current_function().function_type == FunctionType::Initializer {
+                self.parse_error(self.previous.clone(), ErrorCode::ReturnValueFromInitializer, format!("Can't return a value from an initializer."));
+            }
+            self.expression();
+            self.consume(TokenType::SEMICOLON, format!("Expect ';' after return value."));
+            self.emit_byte(OpCode::Return);
         }
     }
 
@@ -247,15 +522,20 @@ impl Compiler {
         self.consume(TokenType::RIGHT_PAREN, format!("Expect ')' after switched value."));
         
         self.consume(TokenType::LEFT_BRACE, format!("Expect '{{' at start of switch."));
+
+        //The switched value is kept as a hidden local so break and continue unwind it
+        self.begin_scope();
+        self.add_local(synthetic_token(""), false);
+        self.mark_initalized();
         
         let mut case_end_jumps: Vec<usize> = vec![];
         while self.token_match(TokenType::CASE) {
             self.expression();
             self.consume(TokenType::COLON, format!("Expect ':' after 'case'."));
-            let case_jump = self.emit_jump(OpCode::SwitchJump(0xff));
+            let case_jump = self.emit_jump(OpCode::SwitchJump);
             
-            self.statement(self.in_loop);
-            let jump_index = self.emit_jump(OpCode::Jump(0xff));
+            self.statement();
+            let jump_index = self.emit_jump(OpCode::Jump);
             case_end_jumps.push(jump_index);
 
             self.patch_jump(case_jump);
@@ -263,20 +543,20 @@ impl Compiler {
 
         self.consume(TokenType::DEFAULT, format!("Expect 'default' at end of switch."));
         self.consume(TokenType::COLON, format!("Expect ':' after 'swtich'."));
-        self.statement(self.in_loop);
+        self.statement();
 
         self.consume(TokenType::RIGHT_BRACE, format!("Expect '}}' at end of switch."));
 
         for case_end in case_end_jumps {
             self.patch_jump(case_end);
         }
-        self.emit_byte(OpCode::Pop);
+        self.end_scope();
 
 
     }
 
     fn statement_for(&mut self) {
-        self.begin_scope(true);
+        self.begin_scope();
         self.consume(TokenType::LEFT_PAREN, format!("Expect '(' after 'for'."));
 
 
@@ -294,12 +574,12 @@ impl Compiler {
             self.consume(TokenType::SEMICOLON, format!("Expect ';' after loop condition."));
 
             //Jump out of loop if the condition is false;
-            exit_jump = Some(self.emit_jump(OpCode::JumpIfFalse(0xff)));
+            exit_jump = Some(self.emit_jump(OpCode::JumpIfFalse));
             self.emit_byte(OpCode::Pop);
         }
 
         if !self.token_match(TokenType::RIGHT_PAREN) {
-            let body_jump = self.emit_jump(OpCode::Jump(0xff));
+            let body_jump = self.emit_jump(OpCode::Jump);
             let increment_start = self.current_chunk().code.len();
 
             self.expression();
@@ -312,13 +592,15 @@ impl Compiler {
 
         }
 
-        self.statement(true);
+        self.begin_loop(loop_start);
+        self.statement();
         self.emit_loop(loop_start);
 
         if let Some(some_exit_jump) = exit_jump {
             self.patch_jump(some_exit_jump);
             self.emit_byte(OpCode::Pop);
         }
+        self.end_loop();
         self.end_scope();
     }
 
@@ -328,18 +610,25 @@ impl Compiler {
         self.expression();
         self.consume(TokenType::RIGHT_PAREN, format!("Expect ')' after condition."));
 
-        let exit_jump = self.emit_jump(OpCode::JumpIfFalse(0xff));
+        let exit_jump = self.emit_jump(OpCode::JumpIfFalse);
         self.emit_byte(OpCode::Pop);
-        self.statement(true);
+        self.begin_loop(loop_start);
+        self.statement();
         self.emit_loop(loop_start);
 
         self.patch_jump(exit_jump);
         self.emit_byte(OpCode::Pop);
+        self.end_loop();
     }
 
     fn emit_loop(&mut self, loop_start: usize) {
-        let chunk_length = self.current_chunk().code.len();
-        self.emit_byte(OpCode::Loop(chunk_length - loop_start + 1));
+        self.emit_byte(OpCode::Loop);
+        //Jump back over the operand as well
+        let offset = self.current_chunk().code.len() - loop_start + 2;
+        if offset > u16::MAX as usize {
+            self.parse_error(self.previous.clone(), ErrorCode::LimitExceeded, format!("Loop body too large."));
+        }
+        self.emit_short(offset);
     }
 
     fn statement_if(&mut self) {
@@ -347,35 +636,36 @@ impl Compiler {
         self.expression();
         self.consume(TokenType::RIGHT_PAREN, format!("Expect ')' after condition."));
 
-        let then_jump = self.emit_jump(OpCode::JumpIfFalse(0xff));
+        let then_jump = self.emit_jump(OpCode::JumpIfFalse);
         self.emit_byte(OpCode::Pop);
-        self.statement(self.in_loop);
+        self.statement();
 
-        let else_jump = self.emit_jump(OpCode::Jump(0xff));
+        let else_jump = self.emit_jump(OpCode::Jump);
         self.patch_jump(then_jump);
         self.emit_byte(OpCode::Pop);
 
         if self.token_match(TokenType::ELSE) {
-            self.statement(self.in_loop);
+            self.statement();
         }
         self.patch_jump(else_jump);
     }
 
+    //Emits a jump with a placeholder operand and returns the operand's offset for patching
     fn emit_jump(&mut self, instruction: OpCode) -> usize {
         self.emit_byte(instruction);
-        return self.current_chunk().code.len()-1;
+        self.emit_short(0xffff);
+        return self.current_chunk().code.len() - 2;
     }
 
     fn patch_jump(&mut self, offset: usize) {
-        let jump_size = self.current_chunk().code.len() - 1 -offset;
-        let (opcode, line) = &self.current_chunk().code[offset];
-        match opcode {
-            OpCode::JumpIfFalse(_) => self.current_chunk().code[offset] = (OpCode::JumpIfFalse(jump_size), *line),
-            OpCode::Jump(_) => self.current_chunk().code[offset] = (OpCode::Jump(jump_size), *line),
-            OpCode::SwitchJump(_) => self.current_chunk().code[offset] = (OpCode::SwitchJump(jump_size), *line),
-            _ => panic!("Attempting to patch the jump of non jump opcode")
+        //Jumps are relative to the end of the operand
+        let jump_size = self.current_chunk().code.len() - offset - 2;
+        if jump_size > u16::MAX as usize {
+            self.parse_error(self.previous.clone(), ErrorCode::LimitExceeded, format!("Too much code to jump over."));
         }
-        
+        let chunk = self.current_chunk();
+        chunk.code[offset] = (jump_size >> 8) as u8;
+        chunk.code[offset + 1] = jump_size as u8;
     }
 
     fn statement_block(&mut self) {
@@ -392,26 +682,42 @@ impl Compiler {
         self.emit_byte(OpCode::Print)
     }
 
+    //Anything that isn't a declaration or a statement keyword starts an expression statement
+    fn at_expression_statement(&self) -> bool {
+        match self.current.token_type {
+            TokenType::CLASS | TokenType::FUN | TokenType::VAR | TokenType::CONST | TokenType::PRINT | TokenType::IF
+                | TokenType::RETURN | TokenType::SWITCH | TokenType::WHILE | TokenType::FOR | TokenType::LEFT_BRACE
+                | TokenType::BREAK | TokenType::CONTINUE | TokenType::SEMICOLON => false,
+            _ => true
+        }
+    }
+
+    fn repl_expression(&mut self) {
+        self.expression();
+        if self.current.token_type != TokenType::EOF {
+            self.consume(TokenType::SEMICOLON, format!("Expect ';' after expression."));
+        }
+        self.emit_byte(OpCode::Print);
+        if self.panic_mode {
+            self.synchronize();
+        }
+    }
+
     fn statement_expression(&mut self) {
         self.expression();
         self.consume(TokenType::SEMICOLON, format!("Expect ';' after expression."));
         self.emit_byte(OpCode::Pop);
     }
 
-    fn begin_scope(&mut self, in_loop: bool) {
-        self.scope_depth += 1;
-        self.in_loop = in_loop;
+    fn begin_scope(&mut self) {
+        self.current_function().scope_depth += 1;
     }
 
     fn end_scope(&mut self) {
-        self.scope_depth -= 1;
-        let mut local_count = self.locals.len();
-        while local_count > 0 && self.locals[local_count - 1].depth > self.scope_depth {
-            self.emit_byte(OpCode::Pop);
-            self.locals.pop();
-            local_count -= 1;
-        }
-        self.in_loop = false;
+        self.current_function().scope_depth -= 1;
+        let scope_depth = self.current_function().scope_depth;
+        self.discard_locals(scope_depth);
+        self.current_function().locals.retain(|local| local.depth <= scope_depth);
     }
 
     fn token_match(&mut self, token_type: TokenType) -> bool {
@@ -440,56 +746,87 @@ impl Compiler {
                 
             }
             if can_assign && self.token_match(TokenType::EQUAL) {
-                self.parse_error(self.previous.clone(), Some(format!("Invalid assignment target.")));
+                self.parse_error(self.previous.clone(), ErrorCode::InvalidAssignmentTarget, format!("Invalid assignment target."));
             }
 
         } else {
-            self.parse_error(self.previous.clone(), Some(format!("Expect expression.")))
+            self.parse_error(self.previous.clone(), ErrorCode::ExpectExpression, format!("Expect expression."))
         }
     }
 
     fn emit_constant(&mut self, value: Value) {
-        self.current_chunk().constant.push(value);
-        let chunk_length = self.current_chunk().constant.len();
-        self.emit_byte(OpCode::Constant(chunk_length - 1));
+        let constant = self.constant_write(value);
+        if constant <= u8::MAX as usize {
+            self.emit_byte(OpCode::Constant);
+            self.emit_operand(constant as u8);
+        } else if constant < 1 << 24 {
+            self.emit_byte(OpCode::ConstantLong);
+            self.emit_operand((constant >> 16) as u8);
+            self.emit_short(constant & 0xffff);
+        } else {
+            self.parse_error(self.previous.clone(), ErrorCode::LimitExceeded, format!("Too many constants in one chunk."));
+        }
     }
 
     fn named_variable(&mut self, token: &Token, can_assign: bool) {    
         let get_op: OpCode;
         let set_op: OpCode;
-
-        if let Some(arg) = self.resolve_local(&token) {
-            get_op = OpCode::GetLocal(arg);
-            set_op = OpCode::SetLocal(arg);
-            if self.locals[arg].is_const {
-                self.parse_error(self.previous.clone(), Some(format!("Can't reassign constant variable.")));
-            }
+        let arg: usize;
+        let mut is_const = false;
+        //Where a local was declared, for the note on reassigning a constant
+        let mut declaration: Option<Token> = None;
+
+        let function_index = self.functions.len() - 1;
+        if let Some(local) = self.resolve_local(function_index, &token) {
+            get_op = OpCode::GetLocal;
+            set_op = OpCode::SetLocal;
+            arg = local;
+            is_const = self.current_function().locals[arg].is_const;
+            declaration = Some(self.current_function().locals[arg].name.clone());
+        } else if let Some(upvalue) = self.resolve_upvalue(function_index, &token) {
+            get_op = OpCode::GetUpvalue;
+            set_op = OpCode::SetUpvalue;
+            arg = upvalue;
+            is_const = self.current_function().upvalues[arg].is_const;
         } else {
-            let name_arg = self.identifier_constant(&token);
-            get_op = OpCode::GetGlobal(name_arg.clone());
-            set_op = OpCode::SetGlobal(name_arg);
+            get_op = OpCode::GetGlobal;
+            set_op = OpCode::SetGlobal;
+            arg = self.identifier_constant(&token);
         }
     
     
-        if can_assign {
-            if self.token_match(TokenType::EQUAL) {
-                self.expression();
-                self.emit_byte(set_op)
-            } else {
-                self.emit_byte(get_op);
+        if can_assign && self.token_match(TokenType::EQUAL) {
+            if is_const {
+                let mut diagnostic = Diagnostic::at(&self.previous, ErrorCode::ReassignConstant, format!("Can't reassign constant variable."));
+                if let Some(declaration) = &declaration {
+                    diagnostic = diagnostic.with_note(format!("'{}' was declared const here", declaration.lexeme), Some(declaration));
+                }
+                self.report(diagnostic);
             }
+            self.expression();
+            self.emit_variable_op(set_op, arg);
+        } else {
+            self.emit_variable_op(get_op, arg);
+        }
+    }
+
+    //Globals are addressed by name constant, locals and upvalues by a single byte index
+    fn emit_variable_op(&mut self, op_code: OpCode, arg: usize) {
+        if op_code == OpCode::GetGlobal || op_code == OpCode::SetGlobal {
+            self.emit_with_constant(op_code, arg);
         } else {
-            self.parse_error(self.current.clone(), Some(format!("Invalid assignment target.")));
+            self.emit_byte(op_code);
+            self.emit_operand(arg as u8);
         }
     }
 
-    fn resolve_local(&mut self, token: &Token) -> Option<usize> {
-        let mut local_count = self.locals.len();
+    fn resolve_local(&mut self, function_index: usize, token: &Token) -> Option<usize> {
+        let mut local_count = self.functions[function_index].locals.len();
         while local_count > 0 {
-            if let Some(value) = self.locals.get(local_count - 1) {
+            if let Some(value) = self.functions[function_index].locals.get(local_count - 1) {
                 if value.name.lexeme == *token.lexeme {
-                    if self.locals[local_count-1].depth == -1 {
-                        self.parse_error(self.previous.clone(), Some(format!("Can't read local variable in its own initializer.")));
+                    if self.functions[function_index].locals[local_count-1].depth == -1 {
+                        self.parse_error(self.previous.clone(), ErrorCode::ReadInOwnInitializer, format!("Can't read local variable in its own initializer."));
                     }
                     return Some(local_count-1)
                 }
@@ -499,26 +836,77 @@ impl Compiler {
         return None
     }
 
-    fn parse_error_token(&mut self, token: Token) {
-        if let Some(error_literal) = &token.literal {
-            if let Literal::Str(error_message) = error_literal {
-                self.parse_error(token.clone(), Some(error_message.to_string()));
-                return;
+    fn resolve_upvalue(&mut self, function_index: usize, token: &Token) -> Option<usize> {
+        if function_index == 0 {
+            return None
+        }
+
+        if let Some(local) = self.resolve_local(function_index - 1, token) {
+            self.functions[function_index - 1].locals[local].is_captured = true;
+            let is_const = self.functions[function_index - 1].locals[local].is_const;
+            return Some(self.add_upvalue(function_index, Upvalue { index: local, is_local: true, is_const }));
+        }
+
+        if let Some(upvalue) = self.resolve_upvalue(function_index - 1, token) {
+            let is_const = self.functions[function_index - 1].upvalues[upvalue].is_const;
+            return Some(self.add_upvalue(function_index, Upvalue { index: upvalue, is_local: false, is_const }));
+        }
+        return None
+    }
+
+    fn add_upvalue(&mut self, function_index: usize, upvalue: Upvalue) -> usize {
+        let upvalues = &self.functions[function_index].upvalues;
+        //Closures referencing the same variable more than once share a single upvalue
+        if let Some(existing) = upvalues.iter().position(|existing| existing.index == upvalue.index && existing.is_local == upvalue.is_local) {
+            return existing
+        }
+
+        if upvalues.len() == 256 {
+            self.parse_error(self.previous.clone(), ErrorCode::LimitExceeded, format!("Too many closure variables in function."));
+            return 0
+        }
+
+        self.functions[function_index].upvalues.push(upvalue);
+        self.functions[function_index].function.upvalue_count += 1;
+        return self.functions[function_index].upvalues.len() - 1;
+    }
+
+    fn argument_list(&mut self) -> usize {
+        let mut arg_count = 0;
+        if self.current.token_type != TokenType::RIGHT_PAREN {
+            loop {
+                self.expression();
+                if arg_count == 255 {
+                    self.parse_error(self.previous.clone(), ErrorCode::LimitExceeded, format!("Can't have more than 255 arguments."));
+                }
+                arg_count += 1;
+                if !self.token_match(TokenType::COMMA) {
+                    break;
+                }
             }
         }
-        self.parse_error(token, None);
+        self.consume(TokenType::RIGHT_PAREN, format!("Expect ')' after arguments."));
+        return arg_count;
+    }
+
+    fn parse_error_token(&mut self, token: Token) {
+        if let Some(Literal::Str(error_message)) = &token.literal {
+            let diagnostic = Diagnostic::at(&token, token.error_code(), error_message.to_string());
+            self.report(diagnostic);
+        }
     }
 
+    fn parse_error(&mut self, token: Token, code: ErrorCode, error_message: String) {
+        self.report(Diagnostic::at(&token, code, error_message));
+    }
 
-    fn parse_error(&mut self, token: Token, error_message: Option<String>) {
+    //Only the first error is kept until the compiler has synchronized
+    fn report(&mut self, diagnostic: Diagnostic) {
         if self.panic_mode {
             return;
         }
         self.panic_mode = true;
-        self.error_message.push(format!("[Line {}] Error at '{}'", token.line, token.lexeme));
-        if let Some(error_message_set) = error_message {
-            self.error_message.push(format!("Error Message: {}", error_message_set));
-        } 
+        self.diagnostics.push(diagnostic);
     }
 
     fn synchronize(&mut self) {
@@ -561,7 +949,7 @@ const PRECEDENCE: Precedence = Precedence {
 
 fn get_rules(token: TokenType) -> Rule {
     match token {
-        TokenType::LEFT_PAREN => Rule{prefix: Some(grouping), infix: None, precedence: PRECEDENCE.none },
+        TokenType::LEFT_PAREN => Rule{prefix: Some(grouping), infix: Some(call), precedence: PRECEDENCE.call },
         TokenType::MINUS => Rule{prefix: Some(unary), infix: Some(binary), precedence: PRECEDENCE.term },
         TokenType::PLUS => Rule{prefix: None, infix: Some(binary), precedence: PRECEDENCE.term },
         TokenType::SLASH => Rule{prefix: None, infix: Some(binary), precedence: PRECEDENCE.factor },
@@ -579,6 +967,9 @@ fn get_rules(token: TokenType) -> Rule {
         TokenType::GREATER_EQUAL => Rule{prefix: None, infix: Some(binary), precedence: PRECEDENCE.comparison},
         TokenType::LESS => Rule{prefix: None, infix: Some(binary), precedence: PRECEDENCE.comparison},
         TokenType::LESS_EQUAL => Rule{prefix: None, infix: Some(binary), precedence: PRECEDENCE.comparison},
+        TokenType::DOT => Rule{prefix: None, infix: Some(dot), precedence: PRECEDENCE.call},
+        TokenType::THIS => Rule{prefix: Some(this), infix: None, precedence: PRECEDENCE.none},
+        TokenType::SUPER => Rule{prefix: Some(super_), infix: None, precedence: PRECEDENCE.none},
         TokenType::AND => Rule{prefix: None, infix: Some(and_), precedence: PRECEDENCE.and},
         TokenType::OR => Rule{prefix: None, infix: Some(or_), precedence: PRECEDENCE.or},
         _ => Rule{prefix: None, infix: None, precedence: PRECEDENCE.none}
@@ -611,6 +1002,64 @@ fn grouping(compiler: &mut Compiler, can_assign: bool) {
     compiler.consume(TokenType::RIGHT_PAREN, format!("Excpect ')' after expression."));
 }
 
+fn call(compiler: &mut Compiler, can_assign: bool) {
+    let arg_count = compiler.argument_list();
+    compiler.emit_byte(OpCode::Call);
+    compiler.emit_operand(arg_count as u8);
+}
+
+fn dot(compiler: &mut Compiler, can_assign: bool) {
+    compiler.consume(TokenType::IDENTIFIER, format!("Expect property name after '.'."));
+    let name = compiler.identifier_constant(&compiler.previous.clone());
+
+    if can_assign && compiler.token_match(TokenType::EQUAL) {
+        compiler.expression();
+        compiler.emit_with_constant(OpCode::SetProperty, name);
+    } else if compiler.token_match(TokenType::LEFT_PAREN) {
+        let arg_count = compiler.argument_list();
+        compiler.emit_with_constant(OpCode::Invoke, name);
+        compiler.emit_operand(arg_count as u8);
+    } else {
+        compiler.emit_with_constant(OpCode::GetProperty, name);
+    }
+}
+
+fn this(compiler: &mut Compiler, can_assign: bool) {
+    if compiler.classes.is_empty() {
+        compiler.parse_error(compiler.previous.clone(), ErrorCode::ThisOutsideClass, format!("Can't use 'this' outside of a class."));
+        return;
+    }
+    variable(compiler, false);
+}
+
+fn super_(compiler: &mut Compiler, can_assign: bool) {
+    match compiler.classes.last() {
+        None => compiler.parse_error(compiler.previous.clone(), ErrorCode::InvalidSuper, format!("Can't use 'super' outside of a class.")),
+        Some(class) if !class.has_superclass => compiler.parse_error(compiler.previous.clone(), ErrorCode::InvalidSuper, format!("Can't use 'super' in a class with no superclass.")),
+        _ => ()
+    }
+
+    compiler.consume(TokenType::DOT, format!("Expect '.' after 'super'."));
+    compiler.consume(TokenType::IDENTIFIER, format!("Expect superclass method name."));
+    let name = compiler.identifier_constant(&compiler.previous.clone());
+
+    compiler.named_variable(&synthetic_token("this"), false);
+    if compiler.token_match(TokenType::LEFT_PAREN) {
+        let arg_count = compiler.argument_list();
+        compiler.named_variable(&synthetic_token("super"), false);
+        compiler.emit_with_constant(OpCode::SuperInvoke, name);
+        compiler.emit_operand(arg_count as u8);
+    } else {
+        compiler.named_variable(&synthetic_token("super"), false);
+        compiler.emit_with_constant(OpCode::GetSuper, name);
+    }
+}
+
+//Token for names the compiler introduces itself such as 'this' and 'super'
+fn synthetic_token(lexeme: &str) -> Token {
+    Token::synthetic(TokenType::IDENTIFIER, lexeme, 0)
+}
+
 fn value_literal(compiler: &mut Compiler, can_assign: bool) {
     //TODO: better error handling here (we can assume for now value set)
     let value = &compiler.previous.literal;
@@ -620,7 +1069,8 @@ fn value_literal(compiler: &mut Compiler, can_assign: bool) {
                 compiler.emit_constant(Value::Number(*num)); 
             },
             Literal::Str(str) => {
-                compiler.emit_constant(Value::String(format!("{}",str)));
+                let string = compiler.heap.intern(format!("{}",str));
+                compiler.emit_constant(Value::String(string));
             }
         }
     }
@@ -676,15 +1126,15 @@ fn variable(compiler: &mut Compiler, can_assign: bool) {
 }
 
 fn and_(compiler: &mut Compiler, can_assign: bool) {
-    let end_jump = compiler.emit_jump(OpCode::JumpIfFalse(0xff));
+    let end_jump = compiler.emit_jump(OpCode::JumpIfFalse);
     compiler.emit_byte(OpCode::Pop);
     compiler.parse_precedence(PRECEDENCE.and);
     compiler.patch_jump(end_jump);
 }
 
 fn or_(compiler: &mut Compiler, can_assign: bool) {
-    let else_jump = compiler.emit_jump(OpCode::JumpIfFalse(0xff));
-    let end_jump = compiler.emit_jump(OpCode::Jump(0xff));
+    let else_jump = compiler.emit_jump(OpCode::JumpIfFalse);
+    let end_jump = compiler.emit_jump(OpCode::Jump);
     
     compiler.patch_jump(else_jump);
     compiler.emit_byte(OpCode::Pop);
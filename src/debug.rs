@@ -1,41 +1,130 @@
-use crate::bytecode::{Chunk, Value, OpCode};
+use crate::{bytecode::{Chunk, Value, OpCode}, heap::Heap};
 
-pub fn disassemble_chunk(chunk: &Chunk, name: &str) {
+pub fn disassemble_chunk(chunk: &Chunk, name: &str, heap: &Heap) {
     print!("== {} ==\n", name);
-    for (op_code, line_number) in chunk.code.iter() {
-        disassemble_instruction(op_code, line_number, &chunk.constant);
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        offset = disassemble_instruction(chunk, offset, heap);
+    }
+
+    //Functions declared in this chunk are stored as constants
+    for constant in chunk.constant.iter() {
+        if let Value::Function(function) = constant {
+            disassemble_chunk(&heap.function(*function).chunk, &heap.format(constant), heap);
+        }
     }
 }
 
-pub fn disassemble_instruction(op_code: &OpCode, line: &usize, constants: &Vec<Value>) {
-    print!("{:<6}", line);
+//Prints the instruction at offset and returns the offset of the next one
+pub fn disassemble_instruction(chunk: &Chunk, offset: usize, heap: &Heap) -> usize {
+    print!("{:04} ", offset);
+    let line = chunk.line(offset);
+    if offset > 0 && line == chunk.line(offset - 1) {
+        print!("   |  ");
+    } else {
+        print!("{:<6}", line);
+    }
+
+    let op_code = match OpCode::from_byte(chunk.code[offset]) {
+        Some(op_code) => op_code,
+        None => {
+            print!("Unknown opcode {}\n", chunk.code[offset]);
+            return offset + 1;
+        }
+    };
     match op_code {
-        OpCode::Return => print!("OP_RETURN\n"),
-        OpCode::Constant(c) => print!("OP_CONSTANT {}\n", constants[*c]),
-        OpCode::Negate => print!("OP_NEGATE\n"),
-        OpCode::Add => print!("OP_ADD\n"),
-        OpCode::Subtract => print!("OP_SUBTRACT\n"),
-        OpCode::Multiply => print!("OP_MULTIPLY\n"),
-        OpCode::Divide => print!("OP_DIVIDE\n"),
-        OpCode::Not => print!("OP_NEGATE\n"),
-        OpCode::Less => print!("OP_LESS\n"),
-        OpCode::Equal => print!("OP_EQUAL\n"),
-        OpCode::Greater => print!("OP_GREATER\n"),
-        OpCode::Print => print!("OP_PRINT\n"),
-        OpCode::Pop => print!("OP_POP\n"),
-        OpCode::DefineGlobal(c,b) => print!("OP_DEFINE_GLOBAL {} {}\n", constants[*c], if *b { "CONST" } else { "VAR" }),
-        OpCode::GetGlobal(n) => print!("OP_GET_GLOBAL {}\n",n),
-        OpCode::SetGlobal(n) => print!("OP_SET_GLOBAL {}\n",n),
-        OpCode::GetLocal(n) => print!("OP_GET_LOCAL {}\n",n),
-        OpCode::SetLocal(n) => print!("OP_SET_LOCAL {}\n",n),
-        OpCode::JumpIfFalse(n) => print!("OP_JUMP_IF_FALSE {}\n",n),
-        OpCode::Jump(n) => print!("OP_JUMP {}\n",n),
-        OpCode::Loop(n) => print!("OP_LOOP {}\n",n),
-        OpCode::SwitchJump(n) => print!("OP_SWITCH_JUMP {}\n",n),
-        OpCode::Break => print!("OP_BREAK\n"),
-        OpCode::Continue => print!("OP_CONTINUE\n"),
+        OpCode::Return => simple_instruction("OP_RETURN", offset),
+        OpCode::Constant => {
+            let constant = chunk.code[offset + 1] as usize;
+            print!("OP_CONSTANT {}\n", heap.format(&chunk.constant[constant]));
+            offset + 2
+        },
+        OpCode::ConstantLong => {
+            let constant = chunk.read_long(offset + 1);
+            print!("OP_CONSTANT_LONG {}\n", heap.format(&chunk.constant[constant]));
+            offset + 4
+        },
+        OpCode::Negate => simple_instruction("OP_NEGATE", offset),
+        OpCode::Add => simple_instruction("OP_ADD", offset),
+        OpCode::Subtract => simple_instruction("OP_SUBTRACT", offset),
+        OpCode::Multiply => simple_instruction("OP_MULTIPLY", offset),
+        OpCode::Divide => simple_instruction("OP_DIVIDE", offset),
+        OpCode::Not => simple_instruction("OP_NOT", offset),
+        OpCode::Less => simple_instruction("OP_LESS", offset),
+        OpCode::Equal => simple_instruction("OP_EQUAL", offset),
+        OpCode::Greater => simple_instruction("OP_GREATER", offset),
+        OpCode::Print => simple_instruction("OP_PRINT", offset),
+        OpCode::Pop => simple_instruction("OP_POP", offset),
+        OpCode::DefineGlobal => {
+            let constant = chunk.read_short(offset + 1);
+            let is_const = chunk.code[offset + 3] == 1;
+            print!("OP_DEFINE_GLOBAL {} {}\n", heap.format(&chunk.constant[constant]), if is_const { "CONST" } else { "VAR" });
+            offset + 4
+        },
+        OpCode::GetGlobal => constant_instruction("OP_GET_GLOBAL", chunk, offset, heap),
+        OpCode::SetGlobal => constant_instruction("OP_SET_GLOBAL", chunk, offset, heap),
+        OpCode::GetLocal => byte_instruction("OP_GET_LOCAL", chunk, offset),
+        OpCode::SetLocal => byte_instruction("OP_SET_LOCAL", chunk, offset),
+        OpCode::JumpIfFalse => jump_instruction("OP_JUMP_IF_FALSE", true, chunk, offset),
+        OpCode::Jump => jump_instruction("OP_JUMP", true, chunk, offset),
+        OpCode::Loop => jump_instruction("OP_LOOP", false, chunk, offset),
+        OpCode::SwitchJump => jump_instruction("OP_SWITCH_JUMP", true, chunk, offset),
+        OpCode::Call => byte_instruction("OP_CALL", chunk, offset),
+        OpCode::Closure => {
+            let constant = chunk.read_short(offset + 1);
+            print!("OP_CLOSURE {}\n", heap.format(&chunk.constant[constant]));
+            let mut offset = offset + 3;
+            if let Value::Function(function) = chunk.constant[constant] {
+                for _ in 0..heap.function(function).upvalue_count {
+                    let is_local = chunk.code[offset] == 1;
+                    let index = chunk.code[offset + 1];
+                    print!("{:04}    |   {} {}\n", offset, if is_local { "local" } else { "upvalue" }, index);
+                    offset += 2;
+                }
+            }
+            offset
+        },
+        OpCode::GetUpvalue => byte_instruction("OP_GET_UPVALUE", chunk, offset),
+        OpCode::SetUpvalue => byte_instruction("OP_SET_UPVALUE", chunk, offset),
+        OpCode::CloseUpvalue => simple_instruction("OP_CLOSE_UPVALUE", offset),
+        OpCode::Class => constant_instruction("OP_CLASS", chunk, offset, heap),
+        OpCode::Method => constant_instruction("OP_METHOD", chunk, offset, heap),
+        OpCode::Inherit => simple_instruction("OP_INHERIT", offset),
+        OpCode::GetProperty => constant_instruction("OP_GET_PROPERTY", chunk, offset, heap),
+        OpCode::SetProperty => constant_instruction("OP_SET_PROPERTY", chunk, offset, heap),
+        OpCode::Invoke => invoke_instruction("OP_INVOKE", chunk, offset, heap),
+        OpCode::GetSuper => constant_instruction("OP_GET_SUPER", chunk, offset, heap),
+        OpCode::SuperInvoke => invoke_instruction("OP_SUPER_INVOKE", chunk, offset, heap),
+    }
+}
+
+fn simple_instruction(name: &str, offset: usize) -> usize {
+    print!("{}\n", name);
+    return offset + 1;
+}
 
+fn byte_instruction(name: &str, chunk: &Chunk, offset: usize) -> usize {
+    print!("{} {}\n", name, chunk.code[offset + 1]);
+    return offset + 2;
+}
 
+fn constant_instruction(name: &str, chunk: &Chunk, offset: usize, heap: &Heap) -> usize {
+    let constant = chunk.read_short(offset + 1);
+    print!("{} {}\n", name, heap.format(&chunk.constant[constant]));
+    return offset + 3;
+}
 
-    }
-}
\ No newline at end of file
+fn invoke_instruction(name: &str, chunk: &Chunk, offset: usize, heap: &Heap) -> usize {
+    let constant = chunk.read_short(offset + 1);
+    let arg_count = chunk.code[offset + 3];
+    print!("{} ({} args) {}\n", name, arg_count, heap.format(&chunk.constant[constant]));
+    return offset + 4;
+}
+
+//Prints where the jump lands rather than its distance
+fn jump_instruction(name: &str, forward: bool, chunk: &Chunk, offset: usize) -> usize {
+    let jump = chunk.read_short(offset + 1);
+    let target = if forward { offset + 3 + jump } else { offset + 3 - jump };
+    print!("{} {} -> {}\n", name, offset, target);
+    return offset + 3;
+}
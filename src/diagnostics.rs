@@ -0,0 +1,130 @@
+use std::ops::Range;
+
+use crate::scanner::Token;
+
+//Stable codes so tools and documentation can refer to an error without matching on its message
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorCode {
+    UnexpectedCharacter,
+    UnterminatedString,
+    ExpectExpression,
+    //A required token such as ';' or ')' is missing
+    ExpectToken,
+    InvalidAssignmentTarget,
+    DuplicateVariable,
+    ReadInOwnInitializer,
+    ReassignConstant,
+    ReturnFromTopLevel,
+    ReturnValueFromInitializer,
+    ThisOutsideClass,
+    InvalidSuper,
+    LoopControlOutsideLoop,
+    InheritFromSelf,
+    //One of the compiler's limits, such as 255 parameters, was exceeded
+    LimitExceeded
+}
+
+impl ErrorCode {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorCode::UnexpectedCharacter => "E0001",
+            ErrorCode::UnterminatedString => "E0002",
+            ErrorCode::ExpectExpression => "E0003",
+            ErrorCode::ExpectToken => "E0004",
+            ErrorCode::InvalidAssignmentTarget => "E0005",
+            ErrorCode::DuplicateVariable => "E0006",
+            ErrorCode::ReadInOwnInitializer => "E0007",
+            ErrorCode::ReassignConstant => "E0008",
+            ErrorCode::ReturnFromTopLevel => "E0009",
+            ErrorCode::ReturnValueFromInitializer => "E0010",
+            ErrorCode::ThisOutsideClass => "E0011",
+            ErrorCode::InvalidSuper => "E0012",
+            ErrorCode::LoopControlOutsideLoop => "E0013",
+            ErrorCode::InheritFromSelf => "E0014",
+            ErrorCode::LimitExceeded => "E0015"
+        }
+    }
+}
+
+//Extra context shown under an error, pointing at another place in the source if it has a span
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    pub message: String,
+    pub span: Option<Range<usize>>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub code: ErrorCode,
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    //Byte range of the offending token in the source
+    pub span: Range<usize>,
+    pub lexeme: String,
+    pub notes: Vec<Note>
+}
+
+impl Diagnostic {
+    pub fn at(token: &Token, code: ErrorCode, message: String) -> Diagnostic {
+        return Diagnostic {
+            code,
+            message,
+            line: token.line,
+            column: token.column,
+            span: token.span.clone(),
+            lexeme: token.lexeme.clone(),
+            notes: vec![]
+        };
+    }
+
+    pub fn with_note(mut self, message: String, token: Option<&Token>) -> Diagnostic {
+        self.notes.push(Note { message, span: token.map(|token| token.span.clone()) });
+        return self;
+    }
+
+    //The two line form the test suite and the bytecode compiler have always used
+    pub fn summary(&self) -> Vec<String> {
+        return vec![format!("[Line {}] Error at '{}'", self.line, self.lexeme), format!("Error Message: {}", self.message)];
+    }
+
+    //The error with the offending source line and a caret under the token, followed by any notes:
+    //
+    //error[E0004]: Expect ';' after expression.
+    // --> line 1, column 8
+    //  |
+    //1 | print a
+    //  |        ^
+    pub fn render(&self, source: &str) -> String {
+        let mut rendered = format!("error[{}]: {}\n", self.code.code(), self.message);
+        rendered.push_str(&render_snippet(source, &self.span));
+        for note in self.notes.iter() {
+            match &note.span {
+                Some(span) => {
+                    rendered.push_str(&format!("note: {}\n", note.message));
+                    rendered.push_str(&render_snippet(source, span));
+                },
+                None => rendered.push_str(&format!("  = note: {}\n", note.message))
+            }
+        }
+        return rendered;
+    }
+}
+
+//Renders the line holding the start of the span with the span underlined, multi-line spans are cut at the end of the line
+fn render_snippet(source: &str, span: &Range<usize>) -> String {
+    let start = span.start.min(source.len());
+    let line_start = source[..start].rfind('\n').map(|index| index + 1).unwrap_or(0);
+    let line_end = source[start..].find('\n').map(|index| start + index).unwrap_or(source.len());
+    let line_number = source[..start].matches('\n').count() + 1;
+    let line = source[line_start..line_end].trim_end_matches('\r');
+    let column = source[line_start..start].chars().count() + 1;
+
+    //Tabs are kept in the padding so the caret lines up however wide the terminal draws them
+    let padding: String = source[line_start..start].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+    let end = span.end.clamp(start, line_end);
+    let underline = "^".repeat(source[start..end].chars().count().max(1));
+
+    let gutter = " ".repeat(line_number.to_string().len());
+    return format!("{gutter}--> line {line_number}, column {column}\n{gutter} |\n{line_number} | {line}\n{gutter} | {padding}{underline}\n");
+}
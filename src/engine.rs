@@ -0,0 +1,216 @@
+use std::fmt;
+
+use crate::{bytecode::Value, heap::Heap, native::{type_name, Arity, NativeFn}, output::OutputSink, virtual_machine::{InterpretResult, InterpreterOutput, VirtualMachine}};
+
+//A VM a Rust host can drive: run scripts, call their functions and read or set their globals
+pub struct Lox {
+    vm: VirtualMachine
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LoxError {
+    //The compiler's error lines
+    Compile(Vec<String>),
+    //The runtime error lines, the same ones the command line prints
+    Runtime(Vec<String>),
+    //The script called exit() with this status
+    Exit(i32),
+    UndefinedGlobal(String),
+    //A value couldn't be converted to the Rust type asked for
+    Type(String)
+}
+
+impl fmt::Display for LoxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoxError::Compile(lines) | LoxError::Runtime(lines) => write!(f, "{}", lines.join("\n")),
+            LoxError::Exit(code) => write!(f, "Script exited with status {}.", code),
+            LoxError::UndefinedGlobal(name) => write!(f, "Undefined variable '{}'.", name),
+            LoxError::Type(message) => write!(f, "{}", message)
+        }
+    }
+}
+
+impl std::error::Error for LoxError {}
+
+//Rust values that can be handed to scripts
+pub trait ToValue {
+    fn to_value(&self, heap: &mut Heap) -> Value;
+}
+
+//Rust values that script values can be read back as
+pub trait FromValue: Sized {
+    fn from_value(value: Value, heap: &Heap) -> Result<Self, LoxError>;
+}
+
+impl Default for Lox {
+    fn default() -> Lox {
+        return Lox { vm: VirtualMachine::new() };
+    }
+}
+
+impl Lox {
+    pub fn new() -> Lox {
+        return Lox::default();
+    }
+
+    //Runs a script, the globals it defines stay around for later calls
+    pub fn eval(&mut self, source: &str) -> Result<(), LoxError> {
+        let output = self.vm.interpret_source(source.to_string(), false, false);
+        return self.finish(output);
+    }
+
+    pub fn call<T: FromValue>(&mut self, function_name: &str, args: &[&dyn ToValue]) -> Result<T, LoxError> {
+        let callee = match self.vm.get_global(function_name) {
+            Some(callee) => callee,
+            None => return Err(LoxError::UndefinedGlobal(function_name.to_string()))
+        };
+        //Interning doesn't collect, so the arguments can't be freed before they are on the stack
+        let args: Vec<Value> = args.iter().map(|arg| arg.to_value(&mut self.vm.heap)).collect();
+        match self.vm.call_function(callee, &args) {
+            Ok(result) => return T::from_value(result, &self.vm.heap),
+            Err(output) => return Err(self.finish(output).unwrap_err())
+        }
+    }
+
+    pub fn set_global(&mut self, name: &str, value: impl ToValue) {
+        let value = value.to_value(&mut self.vm.heap);
+        self.vm.set_global(name, value);
+    }
+
+    pub fn get_global<T: FromValue>(&self, name: &str) -> Result<T, LoxError> {
+        match self.vm.get_global(name) {
+            Some(value) => T::from_value(value, &self.vm.heap),
+            None => Err(LoxError::UndefinedGlobal(name.to_string()))
+        }
+    }
+
+    pub fn define_native(&mut self, name: &str, arity: Arity, function: NativeFn) {
+        self.vm.define_native(name, arity, function);
+    }
+
+    //Scripts print to stdout unless given another sink, such as an OutputBuffer, a WriteSink or a closure
+    pub fn set_output(&mut self, output: impl OutputSink + 'static) {
+        self.vm.set_output(Box::new(output));
+    }
+
+    fn finish(&self, output: InterpreterOutput) -> Result<(), LoxError> {
+        match output.result {
+            InterpretResult::InterpretOk => Ok(()),
+            InterpretResult::InterpretExit(code) => Err(LoxError::Exit(code)),
+            InterpretResult::InterpretCompilerError => Err(LoxError::Compile(output.errors)),
+            InterpretResult::InterpretRuntimeError => Err(LoxError::Runtime(output.errors))
+        }
+    }
+}
+
+fn type_error(expected: &str, value: &Value) -> LoxError {
+    return LoxError::Type(format!("Expected a {} but got a {}.", expected, type_name(value)));
+}
+
+impl ToValue for f64 {
+    fn to_value(&self, _heap: &mut Heap) -> Value {
+        return Value::Number(*self);
+    }
+}
+
+impl ToValue for i64 {
+    fn to_value(&self, _heap: &mut Heap) -> Value {
+        return Value::Number(*self as f64);
+    }
+}
+
+impl ToValue for i32 {
+    fn to_value(&self, _heap: &mut Heap) -> Value {
+        return Value::Number(*self as f64);
+    }
+}
+
+impl ToValue for bool {
+    fn to_value(&self, _heap: &mut Heap) -> Value {
+        return Value::Bool(*self);
+    }
+}
+
+impl ToValue for &str {
+    fn to_value(&self, heap: &mut Heap) -> Value {
+        return Value::String(heap.intern(self.to_string()));
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self, heap: &mut Heap) -> Value {
+        return Value::String(heap.intern(self.clone()));
+    }
+}
+
+impl ToValue for () {
+    fn to_value(&self, _heap: &mut Heap) -> Value {
+        return Value::Nil;
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self, heap: &mut Heap) -> Value {
+        match self {
+            Some(value) => value.to_value(heap),
+            None => Value::Nil
+        }
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: Value, _heap: &Heap) -> Result<f64, LoxError> {
+        match value {
+            Value::Number(n) => Ok(n),
+            _ => Err(type_error("number", &value))
+        }
+    }
+}
+
+//Only numbers without a fractional part convert to integers
+impl FromValue for i64 {
+    fn from_value(value: Value, _heap: &Heap) -> Result<i64, LoxError> {
+        match value {
+            Value::Number(n) if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 => Ok(n as i64),
+            _ => Err(type_error("whole number", &value))
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value, _heap: &Heap) -> Result<bool, LoxError> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            _ => Err(type_error("bool", &value))
+        }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value, heap: &Heap) -> Result<String, LoxError> {
+        match value {
+            Value::String(string) => Ok(heap.string(string).clone()),
+            _ => Err(type_error("string", &value))
+        }
+    }
+}
+
+impl FromValue for () {
+    fn from_value(value: Value, _heap: &Heap) -> Result<(), LoxError> {
+        match value {
+            Value::Nil => Ok(()),
+            _ => Err(type_error("nil", &value))
+        }
+    }
+}
+
+//nil reads as None
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value, heap: &Heap) -> Result<Option<T>, LoxError> {
+        match value {
+            Value::Nil => Ok(None),
+            _ => Ok(Some(T::from_value(value, heap)?))
+        }
+    }
+}
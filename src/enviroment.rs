@@ -1,86 +1,69 @@
 use core::fmt;
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::{interpreter::{RuntimeError, Value}, scanner::Token};
 
-#[derive(Clone, Debug)]
+//Environments are shared, a block or call sees the same variables as everything else holding its enclosing environment
+pub type EnviromentRef = Rc<RefCell<Enviroment>>;
+
+#[derive(Debug)]
 pub struct Enviroment {
     pub values: HashMap<String, Value>,
-    pub enclosing: Option<Box<Enviroment>>
+    pub enclosing: Option<EnviromentRef>
 }
 
-pub fn create_enviroment(enclosing: Option<Enviroment>) -> Enviroment {
-    Enviroment {
+pub fn create_enviroment(enclosing: Option<EnviromentRef>) -> EnviromentRef {
+    Rc::new(RefCell::new(Enviroment {
         values: HashMap::new(),
-        enclosing: match enclosing {
-            Some(env) => Some(Box::new(env)),
-            None => None
-        }
-    }
+        enclosing
+    }))
 }
 
-impl Enviroment {
-    pub fn enclosing(&mut self, env: Enviroment) {
-        self.enclosing = Some(Box::new(env));
+//Reads a local from the environment the resolver found it in
+pub fn get_at(enviroment: &EnviromentRef, distance: usize, token: &Token) -> Result<Value, RuntimeError> {
+    match ancestor(enviroment, distance) {
+        Some(enviroment) => enviroment.borrow().get(token),
+        None => Err(RuntimeError::Variable(token.clone(), format!("Undefined variable '{}'.", token.lexeme)))
     }
+}
 
-    pub fn put(&mut self, name: String, value: Value) {
-        self.values.insert(name, value);
+pub fn assign_at(enviroment: &EnviromentRef, distance: usize, token: &Token, new_value: &Value) -> Result<(), RuntimeError> {
+    match ancestor(enviroment, distance) {
+        Some(enviroment) if enviroment.borrow().values.contains_key(&token.lexeme) => {
+            enviroment.borrow_mut().values.insert(token.lexeme.clone(), new_value.clone());
+            Ok(())
+        },
+        _ => Err(RuntimeError::Variable(token.clone(), format!("Undefined variable '{}'.", token.lexeme)))
     }
+}
 
-    pub fn get(&mut self, token: Token, global: &Enviroment) -> Result<Value, RuntimeError> {
-        if let Some(value) = self.values.get(&token.lexeme) {
-            return Ok(value.clone())
-        } else if let Some(env) = &self.enclosing {
-            if let Ok(value) = (*env.clone()).get(token.clone(), global) {
-                return Ok(value.clone())
-            }  else {
-                if let Some(value) = global.values.get(&token.lexeme) {
-                    return Ok(value.clone())
-                } else {
-                    return Err(RuntimeError::Variable(token.clone(), format!("Undefined variable '{:?}'.", token.lexeme)))
-                }
-            }    
-        } else {
-            if let Some(value) = global.values.get(&token.lexeme) {
-                return Ok(value.clone())
-            } else {
-                return Err(RuntimeError::Variable(token.clone(), format!("Undefined variable '{:?}'.", token.lexeme)))
-            }
-        }
-        
-
+fn ancestor(enviroment: &EnviromentRef, distance: usize) -> Option<EnviromentRef> {
+    let mut enviroment = enviroment.clone();
+    for _ in 0..distance {
+        let enclosing = enviroment.borrow().enclosing.clone()?;
+        enviroment = enclosing;
     }
+    return Some(enviroment);
+}
 
-    pub fn assign(&mut self, token: Token, new_value: &Value, global:&mut Enviroment) -> Result<(), RuntimeError> {
-       
-        if global.values.contains_key(&token.lexeme) {
-            global.values.insert(token.lexeme.clone(), new_value.clone());
-            return Ok(());
-        } 
-
-       if self.values.contains_key(&token.lexeme) {
-            self.values.insert(token.lexeme.clone(), new_value.clone());
-            return Ok(());
-        } 
+impl Enviroment {
+    pub fn put(&mut self, name: String, value: Value) {
+        self.values.insert(name, value);
+    }
 
-        match &mut self.enclosing {
-            Some(enclosing) => enclosing.assign(token, new_value, global),
-            None =>  Err(RuntimeError::Variable(token.clone(), format!("Cannot change undefined variable '{:?}'.", token.lexeme)))
+    //Only looks in this environment, used for globals
+    pub fn get(&self, token: &Token) -> Result<Value, RuntimeError> {
+        match self.values.get(&token.lexeme) {
+            Some(value) => Ok(value.clone()),
+            None => Err(RuntimeError::Variable(token.clone(), format!("Undefined variable '{}'.", token.lexeme)))
         }
     }
 
     pub fn assign_global(&mut self, token: Token, new_value: &Value) -> Result<(), RuntimeError> {
-
-       if self.values.contains_key(&token.lexeme) {
+        if self.values.contains_key(&token.lexeme) {
             self.values.insert(token.lexeme.clone(), new_value.clone());
             return Ok(());
-        } 
-
-        match &mut self.enclosing {
-            Some(enclosing) => enclosing.assign_global(token, new_value),
-            None =>  Err(RuntimeError::Variable(token.clone(), format!("Cannot change undefined variable '{:?}'.", token.lexeme)))
         }
+        Err(RuntimeError::Variable(token.clone(), format!("Undefined variable '{}'.", token.lexeme)))
     }
 }
-
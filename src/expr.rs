@@ -11,9 +11,12 @@ pub enum Expr {
     Literal(Literal),
     Logical(Box<Expr>, Token, Box<Expr>),
     Unary(Token, Box<Expr>),
-    Variable(Token),
-    This(Value),
-    Assign(Token, Box<Expr>),
+    //The resolver fills in how many scopes up the variable is, None means it is a global
+    Variable(Token, Option<usize>),
+    This(Token, Option<usize>),
+    //super.method, the depth is where 'super' is defined, 'this' is always one environment closer
+    Super(Token, Token, Option<usize>),
+    Assign(Token, Option<usize>, Box<Expr>),
 }
 #[derive(Clone, PartialEq, Debug)]
 pub enum Literal {
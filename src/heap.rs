@@ -0,0 +1,279 @@
+use std::{collections::HashMap, mem::size_of};
+
+use crate::{bytecode::{BoundMethod, Class, Closure, Instance, Upvalue, Value}, compiler::Function, native::NativeFunction};
+
+//Bytes allocated before the first collection, afterwards the threshold grows with the live heap
+const GC_INITIAL_THRESHOLD: usize = 1024 * 1024;
+const GC_HEAP_GROW_FACTOR: usize = 2;
+
+//Handle to an object living in the heap, copied around instead of the object itself
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ObjRef(usize);
+
+pub enum Object {
+    String(String),
+    Function(Function),
+    Closure(Closure),
+    Upvalue(Upvalue),
+    Class(Class),
+    Instance(Instance),
+    BoundMethod(BoundMethod),
+    Native(NativeFunction)
+}
+
+struct HeapEntry {
+    object: Object,
+    size: usize,
+    is_marked: bool
+}
+
+pub struct Heap {
+    //Freed entries leave a hole that is reused by the next allocation so handles stay stable
+    objects: Vec<Option<HeapEntry>>,
+    free_slots: Vec<usize>,
+    //Every live string by content, so equal strings share one object and compare by handle
+    strings: HashMap<String, ObjRef>,
+    bytes_allocated: usize,
+    next_gc: usize,
+    //Collect on every allocation, used to flush out objects that are not reachable from a root
+    pub stress_gc: bool
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Heap {
+            objects: vec![],
+            free_slots: vec![],
+            strings: HashMap::new(),
+            bytes_allocated: 0,
+            next_gc: GC_INITIAL_THRESHOLD,
+            stress_gc: cfg!(feature = "stress_gc")
+        }
+    }
+}
+
+impl Heap {
+    pub fn alloc(&mut self, object: Object) -> ObjRef {
+        let size = object_size(&object);
+        self.bytes_allocated += size;
+        let entry = HeapEntry { object, size, is_marked: false };
+
+        if let Some(index) = self.free_slots.pop() {
+            self.objects[index] = Some(entry);
+            return ObjRef(index);
+        }
+        self.objects.push(Some(entry));
+        return ObjRef(self.objects.len() - 1);
+    }
+
+    //Returns the existing object for this string if there is one
+    pub fn intern(&mut self, string: String) -> ObjRef {
+        if let Some(object) = self.strings.get(&string) {
+            return *object;
+        }
+        let object = self.alloc(Object::String(string.clone()));
+        self.strings.insert(string, object);
+        return object;
+    }
+
+    //Looks up a string without creating it
+    pub fn find_interned(&self, string: &str) -> Option<ObjRef> {
+        return self.strings.get(string).copied();
+    }
+
+    pub fn should_collect(&self) -> bool {
+        return self.stress_gc || self.bytes_allocated > self.next_gc;
+    }
+
+    //Marks everything reachable from the roots and frees the rest
+    pub fn collect_garbage(&mut self, roots: Vec<ObjRef>) {
+        let mut gray_stack = vec![];
+        for root in roots {
+            self.mark_object(root, &mut gray_stack);
+        }
+
+        while let Some(object) = gray_stack.pop() {
+            for reference in self.references(object) {
+                self.mark_object(reference, &mut gray_stack);
+            }
+        }
+
+        self.sweep();
+        self.next_gc = usize::max(self.bytes_allocated * GC_HEAP_GROW_FACTOR, GC_INITIAL_THRESHOLD);
+    }
+
+    fn mark_object(&mut self, object: ObjRef, gray_stack: &mut Vec<ObjRef>) {
+        if let Some(entry) = self.objects[object.0].as_mut() {
+            if !entry.is_marked {
+                entry.is_marked = true;
+                gray_stack.push(object);
+            }
+        }
+    }
+
+    //Every object the given object keeps alive
+    fn references(&self, object: ObjRef) -> Vec<ObjRef> {
+        match self.get(object) {
+            Object::String(_) | Object::Native(_) => vec![],
+            Object::Function(function) => function.chunk.constant.iter().filter_map(Value::as_object).collect(),
+            Object::Closure(closure) => {
+                let mut references = vec![closure.function];
+                references.extend(closure.upvalues.iter());
+                references
+            },
+            Object::Upvalue(Upvalue::Closed(value)) => value.as_object().into_iter().collect(),
+            Object::Upvalue(Upvalue::Open(_)) => vec![],
+            //Names are marked too, otherwise a freed name could be reused by a different string
+            Object::Class(class) => class.methods.iter().flat_map(|(name, method)| [*name, *method]).collect(),
+            Object::Instance(instance) => {
+                let mut references = vec![instance.class];
+                references.extend(instance.fields.keys());
+                references.extend(instance.fields.values().filter_map(Value::as_object));
+                references
+            },
+            Object::BoundMethod(bound_method) => {
+                let mut references = vec![bound_method.method];
+                references.extend(bound_method.receiver.as_object());
+                references
+            }
+        }
+    }
+
+    fn sweep(&mut self) {
+        for (index, slot) in self.objects.iter_mut().enumerate() {
+            match slot {
+                Some(entry) if entry.is_marked => entry.is_marked = false,
+                Some(entry) => {
+                    //The intern table doesn't keep strings alive
+                    if let Object::String(string) = &entry.object {
+                        self.strings.remove(string);
+                    }
+                    self.bytes_allocated -= entry.size;
+                    self.free_slots.push(index);
+                    *slot = None;
+                },
+                None => ()
+            }
+        }
+    }
+
+    fn get(&self, object: ObjRef) -> &Object {
+        return &self.objects[object.0].as_ref().expect("Object used after being freed").object;
+    }
+
+    fn get_mut(&mut self, object: ObjRef) -> &mut Object {
+        return &mut self.objects[object.0].as_mut().expect("Object used after being freed").object;
+    }
+
+    pub fn string(&self, object: ObjRef) -> &String {
+        match self.get(object) {
+            Object::String(string) => string,
+            _ => panic!("Object is not a string")
+        }
+    }
+
+    pub fn function(&self, object: ObjRef) -> &Function {
+        match self.get(object) {
+            Object::Function(function) => function,
+            _ => panic!("Object is not a function")
+        }
+    }
+
+    pub fn closure(&self, object: ObjRef) -> &Closure {
+        match self.get(object) {
+            Object::Closure(closure) => closure,
+            _ => panic!("Object is not a closure")
+        }
+    }
+
+    pub fn upvalue(&self, object: ObjRef) -> &Upvalue {
+        match self.get(object) {
+            Object::Upvalue(upvalue) => upvalue,
+            _ => panic!("Object is not an upvalue")
+        }
+    }
+
+    pub fn upvalue_mut(&mut self, object: ObjRef) -> &mut Upvalue {
+        match self.get_mut(object) {
+            Object::Upvalue(upvalue) => upvalue,
+            _ => panic!("Object is not an upvalue")
+        }
+    }
+
+    pub fn class(&self, object: ObjRef) -> &Class {
+        match self.get(object) {
+            Object::Class(class) => class,
+            _ => panic!("Object is not a class")
+        }
+    }
+
+    pub fn class_mut(&mut self, object: ObjRef) -> &mut Class {
+        match self.get_mut(object) {
+            Object::Class(class) => class,
+            _ => panic!("Object is not a class")
+        }
+    }
+
+    pub fn instance(&self, object: ObjRef) -> &Instance {
+        match self.get(object) {
+            Object::Instance(instance) => instance,
+            _ => panic!("Object is not an instance")
+        }
+    }
+
+    pub fn instance_mut(&mut self, object: ObjRef) -> &mut Instance {
+        match self.get_mut(object) {
+            Object::Instance(instance) => instance,
+            _ => panic!("Object is not an instance")
+        }
+    }
+
+    pub fn bound_method(&self, object: ObjRef) -> &BoundMethod {
+        match self.get(object) {
+            Object::BoundMethod(bound_method) => bound_method,
+            _ => panic!("Object is not a bound method")
+        }
+    }
+
+    pub fn native(&self, object: ObjRef) -> &NativeFunction {
+        match self.get(object) {
+            Object::Native(native) => native,
+            _ => panic!("Object is not a native function")
+        }
+    }
+
+    //Values only hold handles so printing them needs the heap
+    pub fn format(&self, value: &Value) -> String {
+        match value {
+            Value::Number(n) => format!("{}", n),
+            Value::Bool(b) => format!("{}", b),
+            Value::Nil => format!("nil"),
+            Value::String(s) => self.string(*s).clone(),
+            Value::Function(function) => match &self.function(*function).name {
+                Some(name) => format!("<fn {}>", name),
+                None => format!("<script>")
+            },
+            Value::Closure(closure) => self.format(&Value::Function(self.closure(*closure).function)),
+            Value::Class(class) => self.class(*class).name.clone(),
+            Value::Instance(instance) => format!("{} instance", self.class(self.instance(*instance).class).name),
+            Value::BoundMethod(bound_method) => {
+                let method = self.bound_method(*bound_method).method;
+                self.format(&Value::Closure(method))
+            },
+            Value::Native(_) => format!("<native fn>")
+        }
+    }
+}
+
+//Rough size of an object, only used to decide when to collect
+fn object_size(object: &Object) -> usize {
+    let contents = match object {
+        Object::String(string) => string.len(),
+        Object::Function(function) => function.chunk.code.len() + function.chunk.lines.len() * size_of::<(usize, usize)>() + function.chunk.constant.len() * size_of::<Value>(),
+        Object::Closure(closure) => closure.upvalues.len() * size_of::<ObjRef>(),
+        Object::Class(class) => class.methods.len() * size_of::<ObjRef>() * 2,
+        Object::Instance(instance) => instance.fields.len() * (size_of::<ObjRef>() + size_of::<Value>()),
+        Object::Upvalue(_) | Object::BoundMethod(_) | Object::Native(_) => 0
+    };
+    return size_of::<HeapEntry>() + contents;
+}
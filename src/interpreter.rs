@@ -1,6 +1,6 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use crate::{enviroment::{create_enviroment, Enviroment}, expr::{Expr, Literal}, lox_callable::{Callable, LoxCallable, LoxClass, LoxFunction}, lox_instance::LoxInstance, scanner::{Token, TokenType}, stmt::Stmt};
+use crate::{enviroment::{assign_at, create_enviroment, get_at, EnviromentRef}, expr::{Expr, Literal}, lox_callable::{Callable, LoxCallable, LoxClass, LoxFunction}, lox_instance::LoxInstance, lox_native::{stdlib, NativeFunction}, native::{NativeError, Natives}, output::{OutputSink, StdoutSink}, parser, resolver, scanner::{Scanner, Token, TokenType}, stmt::Stmt, virtual_machine::{InterpretResult, InterpreterOutput, FRAMES_MAX}};
 #[derive(PartialEq, Clone, Debug)]
 pub enum Value {
     Number(f64),
@@ -8,54 +8,123 @@ pub enum Value {
     Bool(bool),
     Nil,
     LoxCallable(Box<LoxCallable>),
-    LoxInstance(LoxInstance)
+    //Instances are shared so every reference sees the same fields
+    LoxInstance(Rc<RefCell<LoxInstance>>)
 }
 
+//Each error carries the token it happened at so it can be reported with its line
 #[derive(Debug)]
 pub enum RuntimeError {
     Variable(Token, String),
-    Type(String),
-    Function(String),
-    Class(String),
-    Return(Option<Value>)
+    Type(Token, String),
+    Function(Token, String),
+    Class(Token, String),
+    Return(Option<Value>),
+    //Raised by exit() to stop the script
+    Exit(i32)
+}
+
+impl RuntimeError {
+    //The same two lines the VM prints, so both backends can be checked against one expectation
+    pub fn report(&self) -> Vec<String> {
+        let (kind, token, message) = match self {
+            RuntimeError::Variable(token, message) => ("Var", token, message),
+            //The VM reports misusing classes and instances as type errors
+            RuntimeError::Type(token, message) | RuntimeError::Class(token, message) => ("Type", token, message),
+            RuntimeError::Function(token, message) => ("Function", token, message),
+            RuntimeError::Return(_) | RuntimeError::Exit(_) => return vec![]
+        };
+        return vec![format!("[Line {}] Runtime {} Error", token.line, kind), format!("Error Message: {}", message)];
+    }
 }
 
-#[derive(Debug)]
 pub struct Interpreter {
-    pub enviroment: Enviroment,
-    pub global: Enviroment
+    //The innermost scope currently executing, the same as global at the top level
+    pub enviroment: EnviromentRef,
+    pub global: EnviromentRef,
+    //Printed values are written here straight away
+    output: Box<dyn OutputSink>,
+    //Function and class calls currently running, limited like the VM's call frames
+    call_depth: usize
 }
 
-pub fn interpret(statements: Vec<Stmt>) {
-    let mut interpreter = Interpreter {
-        global: create_enviroment(None),
-        enviroment: create_enviroment(None)
-    };
-    interpreter.interpret(statements);
+pub fn interpret(statements: Vec<Stmt>) -> Result<(), RuntimeError> {
+    return Interpreter::new().interpret(statements);
 }
 
 impl Interpreter {
-    pub fn interpret(&mut self, statements: Vec<Stmt>) {
-        for statement in statements.iter() {
-            let stmt = self.interpret_statement(statement.clone());
-            if let Err(error) = stmt {
-                print!{"{:?}", error};
-            }
+    pub fn new() -> Interpreter {
+        let global = create_enviroment(None);
+        let mut interpreter = Interpreter {
+            enviroment: global.clone(),
+            global,
+            output: Box::new(StdoutSink),
+            call_depth: 0
+        };
+        for native in stdlib() {
+            interpreter.define_native(native);
         }
+        return interpreter;
     }
 
-    fn format(&mut self, value: Value) -> String {
-        match value {
-            Value::Bool(b) => b.to_string(),
-            Value::Nil => "Nil".to_string(),
-            Value::Number(n) => n.to_string(),
-            Value::String(s) => s,
-            Value::LoxInstance(i) => format!("<class {}>",i.class.name),
-            Value::LoxCallable(c) => match *c {
-                LoxCallable::LoxClass(c) => format!("<class instance {}",c.name),
-                LoxCallable::LoxFunction(f) => format!("<func>")
-            } 
+    //Sends everything printed from now on to the given sink
+    pub fn set_output(&mut self, output: Box<dyn OutputSink>) {
+        self.output = output;
+    }
+
+    //Makes a Rust function callable from scripts as a global
+    pub fn define_native(&mut self, native: NativeFunction) {
+        self.global.borrow_mut().put(native.name.clone(), Value::LoxCallable(Box::new(LoxCallable::NativeFunction(native))));
+    }
+
+    //Scans, parses, resolves and runs a script, reporting errors the same way the VM does
+    pub fn interpret_source(&mut self, source: String) -> InterpreterOutput {
+        return self.run_source(source, false);
+    }
+
+    //Top level expressions print their value like the VM's REPL mode
+    pub fn interpret_repl(&mut self, source: String) -> InterpreterOutput {
+        return self.run_source(source, true);
+    }
+
+    fn run_source(&mut self, source: String, repl: bool) -> InterpreterOutput {
+        let mut scanner = Scanner::default();
+        scanner.scan_tokens(source);
+        let parsed = if repl { parser::parse_repl(scanner.tokens) } else { parser::parse(scanner.tokens) };
+        let statements = match parsed.and_then(|mut statements| resolver::resolve(&mut statements).map(|_| statements)) {
+            Ok(statements) => statements,
+            Err(diagnostics) => {
+                return InterpreterOutput {
+                    result: InterpretResult::InterpretCompilerError,
+                    errors: diagnostics.iter().flat_map(|diagnostic| diagnostic.summary()).collect(),
+                    diagnostics,
+                    stack_trace: vec![]
+                }
+            }
+        };
+        let (result, errors) = match self.interpret(statements) {
+            Ok(()) => (InterpretResult::InterpretOk, vec![]),
+            Err(RuntimeError::Exit(code)) => (InterpretResult::InterpretExit(code), vec![]),
+            Err(error) => (InterpretResult::InterpretRuntimeError, error.report())
+        };
+        return InterpreterOutput {
+            result,
+            errors,
+            diagnostics: vec![],
+            stack_trace: vec![]
+        };
+    }
+
+    //Stops at the first runtime error
+    pub fn interpret(&mut self, statements: Vec<Stmt>) -> Result<(), RuntimeError> {
+        for statement in statements.iter() {
+            if let Err(error) = self.interpret_statement(statement.clone()) {
+                //Leave any blocks the error escaped from so the globals can still be used
+                self.enviroment = self.global.clone();
+                return Err(error);
+            }
         }
+        Ok(())
     }
 
     fn interpret_statement(&mut self, stmt: Stmt) -> Result<(), RuntimeError>  {
@@ -73,80 +142,81 @@ impl Interpreter {
     }
 
     fn interpret_statement_class(&mut self, token: Token, superclass: Option<Expr>, methods: Vec<Stmt>) -> Result<(), RuntimeError> {
-        let mut superclass_final: Option<LoxClass> = None;
+        let mut superclass_final: Option<Rc<LoxClass>> = None;
         if let Some(superclass_expr) = superclass {
+            let superclass_name = match &superclass_expr {
+                Expr::Variable(name, _) => name.clone(),
+                _ => token.clone()
+            };
             let superclass_value = Some(self.interpret_expression(superclass_expr)?);
             if let Some(Value::LoxCallable(superclass_callable)) = superclass_value.clone() {
                 if let LoxCallable::LoxClass(class) = *superclass_callable {
                     superclass_final = Some(class);
                 } else {
-                    return Err(RuntimeError::Class("Superclass must be a class".to_string()))
+                    return Err(RuntimeError::Class(superclass_name, format!("Superclass must be a class.")))
                 }
             } else {
-                return Err(RuntimeError::Class("Superclass must be a class".to_string()))
+                return Err(RuntimeError::Class(superclass_name, format!("Superclass must be a class.")))
             }
         }
         
-        self.global.put(token.lexeme.clone(), Value::Nil);
-        let mut class_methods: HashMap<String, LoxFunction> = HashMap::new();
+        //Methods of a subclass close over an environment defining 'super' as the superclass
+        let enclosing = self.enviroment.clone();
+        if let Some(superclass) = &superclass_final {
+            self.enviroment = create_enviroment(Some(enclosing.clone()));
+            self.enviroment.borrow_mut().put(format!("super"), Value::LoxCallable(Box::new(LoxCallable::LoxClass(superclass.clone()))));
+        }
+
+        let mut class_methods: HashMap<String, Rc<LoxFunction>> = HashMap::new();
         for method in methods {
-            if let Stmt::Function(name, params , code ) = method.clone() {
+            if let Stmt::Function(name, _, _) = &method {
+                let name = name.lexeme.clone();
                 let function = LoxFunction {
-                    stmt: method
+                    is_initializer: name == "init",
+                    stmt: Rc::new(method),
+                    closure: self.enviroment.clone()
                 };
-                class_methods.insert(name.lexeme, function);
+                class_methods.insert(name, Rc::new(function));
             }
         }
+        self.enviroment = enclosing;
         
-        let class = Value::LoxCallable(Box::new(LoxCallable::LoxClass(LoxClass {
+        let class = Value::LoxCallable(Box::new(LoxCallable::LoxClass(Rc::new(LoxClass {
             name: token.lexeme.clone(),
             methods: class_methods,
-            superclass: Box::new(superclass_final)
-        })));
-        self.enviroment.assign(token, &class, &mut self.global)
+            superclass: superclass_final
+        }))));
+        self.define(token.lexeme, class);
+        Ok(())
     }
 
     fn interpret_statement_while(&mut self, condition: Expr, stmt: Stmt) -> Result<(), RuntimeError> {
-        while self.is_truth(condition.clone()) {
+        while self.is_truth(condition.clone())? {
             self.interpret_statement(stmt.clone())?
         } 
         Ok(())
     }
 
-    fn is_truth(&mut self, expr: Expr) -> bool {
-        let value = self.interpret_expression(expr).unwrap();
-        if let Value::Bool(bool_expr) = value {
-            return bool_expr
-        } else {
-            return false
-        }
+    //nil and false are falsey, everything else is truthy
+    fn is_truth(&mut self, expr: Expr) -> Result<bool, RuntimeError> {
+        let value = self.interpret_expression(expr)?;
+        return Ok(is_truthy(&value));
     }
     fn intepret_statement_if(&mut self, condition: Expr, if_stmt: Stmt, else_stmt: Option<Box<Stmt>>) -> Result<(), RuntimeError> {
-        let eval_condition = self.interpret_expression(condition).unwrap();
-        if let Value::Bool(bool_eval_condition) = eval_condition {
-            if bool_eval_condition {
-                self.interpret_statement(if_stmt)?
-            } else if let Some(else_stmt_defined) = else_stmt {
-                self.interpret_statement(*else_stmt_defined)?
-            }
-        } else {
-            print!("If statement cannot evaluate non boolean expression")
+        if self.is_truth(condition)? {
+            self.interpret_statement(if_stmt)?
+        } else if let Some(else_stmt_defined) = else_stmt {
+            self.interpret_statement(*else_stmt_defined)?
         }
         Ok(())
     }
     
-    pub fn interpret_statement_block(&mut self, stmts: Vec<Stmt>, env: Enviroment) -> Result<(), RuntimeError> {
-        self.enviroment = env;
-
-        for stmt in stmts {
-            self.interpret_statement(stmt)?;
-        }
-        if let Some(enclosing) = self.enviroment.enclosing.clone() {
-            self.enviroment = *enclosing
-        } else {
-            panic!("Invalid enviroment");
-        }
-        Ok(())
+    //Runs the statements in env, the previous environment is restored even if one of them fails or returns
+    pub fn interpret_statement_block(&mut self, stmts: Vec<Stmt>, env: EnviromentRef) -> Result<(), RuntimeError> {
+        let previous = std::mem::replace(&mut self.enviroment, env);
+        let result = stmts.into_iter().try_for_each(|stmt| self.interpret_statement(stmt));
+        self.enviroment = previous;
+        return result;
     }
 
     fn interpret_statement_variable(&mut self, token: Token, expr: Expr) -> Result<(), RuntimeError>  {
@@ -155,26 +225,29 @@ impl Interpreter {
             value = self.interpret_expression(expr)?;
         }
 
-        match &self.enviroment.enclosing {
-            Some(env) => self.enviroment.put(token.lexeme, value),
-            None => self.global.put(token.lexeme, value),
-        } 
+        self.define(token.lexeme, value);
         Ok(())
     }
+
+    //At the top level the current environment is the global one
+    fn define(&mut self, name: String, value: Value) {
+        self.enviroment.borrow_mut().put(name, value);
+    }
     fn interpret_statement_expression(&mut self, expr: Expr) -> Result<(), RuntimeError>  {
         let value = self.interpret_expression(expr)?;
         Ok(())
     }
 
     fn interpret_statement_function(&mut self, name: Token, params: Vec<Token>, code: Vec<Stmt>) -> Result<(), RuntimeError>   {
-        let func = Value::LoxCallable(Box::new(LoxCallable::LoxFunction(LoxFunction { stmt: Stmt::Function(name.clone(), params, code)  })));
-        self.global.put(name.lexeme, func);
+        let func = Value::LoxCallable(Box::new(LoxCallable::LoxFunction(LoxFunction { stmt: Rc::new(Stmt::Function(name.clone(), params, code)), closure: self.enviroment.clone(), is_initializer: false })));
+        self.define(name.lexeme, func);
         Ok(())
     }
     
     fn interpret_statement_print(&mut self, expr: Expr) -> Result<(), RuntimeError> {
         let value = self.interpret_expression(expr)?;
-        print!("{:?}\n", self.format(value));
+        let line = format_value(&value);
+        self.output.write_line(&line);
         Ok(())
     }
 
@@ -194,49 +267,59 @@ impl Interpreter {
             Expr::Unary(o, e) => self.interpret_unary(o, *e),
             Expr::Binary(l, o, r) => self.interpret_binary(*l, o, *r),
             Expr::Literal(l) => Ok(self.interpret_literal(l)),
-            Expr::Variable(t) => self.interpret_expression_variable(t),
-            Expr::Assign(t, e) => self.interpret_expression_assignment(t, *e),
+            Expr::Variable(t, d) => self.interpret_expression_variable(t, d),
+            Expr::Assign(t, d, e) => self.interpret_expression_assignment(t, d, *e),
             Expr::Logical(l, o, r) => self.interpret_expression_logical(*l, o, *r),
             Expr::Call(c, p, a) => self.interpret_expression_call(*c, p, a),
-            Expr::This(v) => Ok(v)
+            //Bound methods define 'this' in the environment just outside their body
+            Expr::This(t, d) => self.interpret_expression_variable(t, d),
+            Expr::Super(k, m, d) => self.interpret_super(k, m, d)
         }
     }
 
     fn interpret_set(&mut self, object_expr: Expr, name: Token, value: Expr) -> Result<Value, RuntimeError> {
-        
-        let var_token = match object_expr.clone() {
-            Expr::Variable(t) => t,
-            _ => panic!("Error: cant set property on non variable")
-        };
-        
         let object = self.interpret_expression(object_expr)?;
-        if let Value::LoxInstance(mut instance) = object {
+        if let Value::LoxInstance(instance) = object {
           let set_value = self.interpret_expression(value)?;
-          instance.set(name, set_value.clone());
-          self.enviroment.assign(var_token, &Value::LoxInstance(instance) , &mut self.global)?;
+          instance.borrow_mut().set(name, set_value.clone());
           return Ok(set_value);
         } else {
-          return Err(RuntimeError::Class("Only instance have fields".to_string()));
+          return Err(RuntimeError::Class(name, format!("Only instances have fields.")));
         }
-        
       }
 
+    //Looks the method up on the superclass and binds it to the current 'this'
+    fn interpret_super(&mut self, keyword: Token, method: Token, depth: Option<usize>) -> Result<Value, RuntimeError> {
+        let distance = depth.expect("Error: 'super' wasn't resolved");
+        let superclass = match get_at(&self.enviroment, distance, &keyword)? {
+            Value::LoxCallable(callable) => match *callable {
+                LoxCallable::LoxClass(class) => class,
+                _ => return Err(RuntimeError::Class(keyword, format!("Superclass must be a class.")))
+            },
+            _ => return Err(RuntimeError::Class(keyword, format!("Superclass must be a class.")))
+        };
+        let instance = match get_at(&self.enviroment, distance - 1, &Token::synthetic(TokenType::THIS, "this", keyword.line))? {
+            Value::LoxInstance(instance) => instance,
+            _ => return Err(RuntimeError::Class(keyword, format!("Only instances have methods.")))
+        };
+        match superclass.find_method(&method.lexeme) {
+            Some(function) => Ok(Value::LoxCallable(Box::new(LoxCallable::LoxFunction(function.bind(instance))))),
+            None => Err(RuntimeError::Variable(method.clone(), format!("Undefined property '{}'.", method.lexeme)))
+        }
+    }
+
     fn interpret_get(&mut self, object: Expr, name: Token, ) -> Result<Value, RuntimeError> {
       let object = self.interpret_expression(object)?;
       if let Value::LoxInstance(instance) = object {
-        return instance.get(name)
+        return LoxInstance::get(&instance, name)
       } else {
-        return Err(RuntimeError::Class("Only instance have properties".to_string()));
+        return Err(RuntimeError::Class(name, format!("Only instances have properties.")));
       }
       
     }
 
     fn interpret_expression_call(&mut self, call: Expr, paren: Token, arguments: Vec<Expr>) -> Result<Value, RuntimeError> {
-      let callable_var = match call {
-        Expr::Variable(token) => self.interpret_expression_variable(token),
-        Expr::Get(expr, token) => self.interpret_get(*expr, token),
-        _ => Err(RuntimeError::Type("Attempting to call non functions and classes".to_string()))
-      }?;
+      let callable_var = self.interpret_expression(call)?;
 
       let mut arguments_interpreted: Vec<Value> = vec![];
 
@@ -247,54 +330,79 @@ impl Interpreter {
       if let Value::LoxCallable(lox_callable) = callable_var {
         match *lox_callable {
             LoxCallable::LoxClass(class) => {
-                return Ok(class.call_function(self, arguments_interpreted))
+                let class_arity = class.clone().arity();
+                if class_arity != arguments_interpreted.len() {
+                    return Err(RuntimeError::Function(paren, format!("Expected {} arguments but got {}.", class_arity, arguments_interpreted.len())))
+                }
+                return self.call_deeper(&paren, |interpreter| class.call_function(interpreter, arguments_interpreted))
             },
             LoxCallable::LoxFunction(func) => {
                 let func_arity = func.clone().arity(); 
                 if func_arity == arguments_interpreted.len() {
-                    return Ok(func.call_function(self, arguments_interpreted));
+                    return self.call_deeper(&paren, |interpreter| func.call_function(interpreter, arguments_interpreted));
                 } else {
-                    Err(RuntimeError::Function(format!("Expected {} arguments but got {}.",func_arity, arguments_interpreted.len())))
+                    Err(RuntimeError::Function(paren, format!("Expected {} arguments but got {}.",func_arity, arguments_interpreted.len())))
                 }   
+            },
+            LoxCallable::NativeFunction(native) => {
+                native.arity.check(arguments_interpreted.len()).map_err(|message| RuntimeError::Function(paren.clone(), message))?;
+                //Natives don't know where they were called from, so their errors take the line of the call
+                return (native.function)(self, &arguments_interpreted).map_err(|error| match error {
+                    NativeError::Message(message) => RuntimeError::Function(paren, message),
+                    NativeError::Exit(code) => RuntimeError::Exit(code)
+                });
             }
         }
         
       } else {
-        Err(RuntimeError::Type("Attempting to call non functions and classes".to_string()))
+        Err(RuntimeError::Type(paren, format!("Can only call functions and classes.")))
       }
     
     }
 
+    //Deep recursion reports a runtime error instead of overflowing the Rust stack
+    fn call_deeper(&mut self, paren: &Token, call: impl FnOnce(&mut Interpreter) -> Result<Value, RuntimeError>) -> Result<Value, RuntimeError> {
+        //The script itself takes the first of the VM's frames
+        if self.call_depth + 1 == FRAMES_MAX {
+            return Err(RuntimeError::Function(paren.clone(), format!("Stack overflow.")));
+        }
+        self.call_depth += 1;
+        let result = call(self);
+        self.call_depth -= 1;
+        return result;
+    }
+
     fn interpret_expression_logical(&mut self, left: Expr, operator: Token, right: Expr) -> Result<Value, RuntimeError> {
         let left = self.interpret_expression(left)?;
         if operator.token_type == TokenType::OR {
-            if let Value::Bool(left_val) = left {
-                if left_val {
-                    return Ok(left);
-                }
-            }
-        } else {
-            if let Value::Bool(left_val) = left {
-                if !left_val {
-                    return Ok(left);
-                }
+            if is_truthy(&left) {
+                return Ok(left);
             }
+        } else if !is_truthy(&left) {
+            return Ok(left);
         }
         return self.interpret_expression(right);
     }
 
-    fn interpret_expression_assignment(&mut self, token: Token, expr: Expr) -> Result<Value, RuntimeError> {
+    fn interpret_expression_assignment(&mut self, token: Token, depth: Option<usize>, expr: Expr) -> Result<Value, RuntimeError> {
         let value = self.interpret_expression(expr)?;
-        match &self.enviroment.enclosing {
-            Some(env) => self.enviroment.assign(token, &value, &mut self.global)?,
-            None => self.global.assign_global(token, &value)?,
-        } 
-        
+        self.assign_variable(token, depth, &value)?;
         Ok(value)
     }
+
+    fn assign_variable(&mut self, token: Token, depth: Option<usize>, value: &Value) -> Result<(), RuntimeError> {
+        match depth {
+            Some(distance) => assign_at(&self.enviroment, distance, &token, value),
+            None => self.global.borrow_mut().assign_global(token, value)
+        }
+    }
     
-    fn interpret_expression_variable(&mut self, token: Token) -> Result<Value, RuntimeError> {
-        return self.enviroment.get(token, &self.global);
+    //depth is how many environments up the resolver found the variable, None for globals
+    fn interpret_expression_variable(&mut self, token: Token, depth: Option<usize>) -> Result<Value, RuntimeError> {
+        match depth {
+            Some(distance) => get_at(&self.enviroment, distance, &token),
+            None => self.global.borrow().get(&token)
+        }
     }
     fn interpret_literal(&mut self, literal: Literal) -> Value {
         match literal {
@@ -321,19 +429,11 @@ impl Interpreter {
             (Value::Number(l), TokenType::GREATER_EQUAL, Value::Number(r)) => Ok(Value::Bool(l>=r)),
             (Value::Number(l), TokenType::LESS, Value::Number(r)) => Ok(Value::Bool(l<r)),
             (Value::Number(l), TokenType::LESS_EQUAL, Value::Number(r)) => Ok(Value::Bool(l<=r)),
-            //Equality
-            (Value::Number(l), TokenType::EQUAL_EQUAL, Value::Number(r)) => Ok(Value::Bool(l==r)),
-            (Value::Number(l), TokenType::BANG_EQUAL, Value::Number(r)) => Ok(Value::Bool(l!=r)),
-            (Value::String(l), TokenType::EQUAL_EQUAL, Value::String(r)) => Ok(Value::Bool(l==r)),
-            (Value::String(l), TokenType::BANG_EQUAL, Value::String(r)) => Ok(Value::Bool(l!=r)),
-            (Value::Nil, TokenType::EQUAL_EQUAL, Value::Nil) => Ok(Value::Bool(true)),
-            (Value::Nil, TokenType::BANG_EQUAL, Value::Nil) => Ok(Value::Bool(false)),
-            (Value::Bool(l), TokenType::EQUAL_EQUAL, Value::Bool(r)) => Ok(Value::Bool(l==r)),
-            (Value::Bool(l), TokenType::BANG_EQUAL, Value::Bool(r)) => Ok(Value::Bool(l!=r)),
-            (l, m, r) => Err(RuntimeError::Type(format!(
-                "invalid operands in binary expression ({:?},{:?},{:?})",
-                l, m, r
-            ))),
+            //Equality, values of different types are never equal
+            (l, TokenType::EQUAL_EQUAL, r) => Ok(Value::Bool(values_equal(&l, &r))),
+            (l, TokenType::BANG_EQUAL, r) => Ok(Value::Bool(!values_equal(&l, &r))),
+            (_, TokenType::PLUS, _) => Err(RuntimeError::Type(operator, format!("Operand must be either both string or number."))),
+            _ => Err(RuntimeError::Type(operator, format!("Operand must be both number."))),
         }
     }
     
@@ -342,15 +442,93 @@ impl Interpreter {
         match operator.token_type {
             TokenType::MINUS => match value {
                 Value::Number(n) => Ok(Value::Number(-n)),
-                _ => Err(RuntimeError::Type(format!("Invalid application of - operator to no numeric type")))
+                _ => Err(RuntimeError::Type(operator, format!("Operand must be a number.")))
             },
             TokenType::BANG => match value {
                 Value::Bool(n) => Ok(Value::Bool(!n)),
-                Value::Number(n) => Ok(Value::Bool(n == 0.0)),
-                _ => Err(RuntimeError::Type(format!("Invalid application of ! opeator to non bool/numeric type")))
+                _ => Err(RuntimeError::Type(operator, format!("Can only negate boolean values.")))
+            }
+            _ => Err(RuntimeError::Type(operator.clone(), format!("Cannot apply unary operation to {:?}", operator.token_type)))
+        }
+    }
+}
+
+impl Natives for Interpreter {
+    type Value = Value;
+
+    fn nil(&self) -> Value {
+        return Value::Nil;
+    }
+
+    fn number(&self, n: f64) -> Value {
+        return Value::Number(n);
+    }
+
+    fn string(&mut self, string: String) -> Value {
+        return Value::String(string);
+    }
+
+    fn as_number(&self, value: &Value) -> Option<f64> {
+        match value {
+            Value::Number(n) => Some(*n),
+            _ => None
+        }
+    }
+
+    fn as_string<'a>(&'a self, value: &'a Value) -> Option<&'a str> {
+        match value {
+            Value::String(string) => Some(string),
+            _ => None
+        }
+    }
+
+    fn format(&self, value: &Value) -> String {
+        return format_value(value);
+    }
+
+    fn type_name(&self, value: &Value) -> &'static str {
+        match value {
+            Value::Number(_) => "number",
+            Value::Bool(_) => "bool",
+            Value::Nil => "nil",
+            Value::String(_) => "string",
+            Value::LoxInstance(_) => "instance",
+            Value::LoxCallable(callable) => match callable.as_ref() {
+                LoxCallable::LoxClass(_) => "class",
+                LoxCallable::LoxFunction(_) | LoxCallable::NativeFunction(_) => "function"
             }
-            _ => Err(RuntimeError::Type(format!("Cannot apply unary operation to {:?}", operator.token_type)))
         }
     }
+
+    fn output(&mut self) -> &mut dyn OutputSink {
+        return self.output.as_mut();
+    }
+}
+
+//Printed the same way as the VM prints its values
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => b.to_string(),
+        Value::Nil => format!("nil"),
+        Value::LoxInstance(i) => format!("{} instance", i.borrow().class.name),
+        Value::LoxCallable(c) => match c.as_ref() {
+            LoxCallable::LoxFunction(f) => format!("{:?}", f),
+            LoxCallable::LoxClass(c) => c.name.clone(),
+            LoxCallable::NativeFunction(_) => format!("<native fn>")
+        }
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    return !matches!(value, Value::Nil | Value::Bool(false));
 }
 
+//Instances are only equal to themselves
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::LoxInstance(l), Value::LoxInstance(r)) => Rc::ptr_eq(l, r),
+        (l, r) => l == r
+    }
+}
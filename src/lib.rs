@@ -0,0 +1,28 @@
+//The Lox interpreters as a library, the lox binary is a command line wrapper around it
+pub mod scanner;
+pub mod diagnostics;
+pub mod expr;
+pub mod parser;
+pub mod resolver;
+pub mod interpreter;
+pub mod stmt;
+pub mod enviroment;
+pub mod lox_callable;
+pub mod lox_instance;
+pub mod lox_native;
+pub mod bytecode;
+pub mod debug;
+pub mod virtual_machine;
+pub mod compiler;
+pub mod heap;
+pub mod serialize;
+pub mod native;
+pub mod output;
+pub mod runner;
+pub mod repl;
+pub mod bench;
+mod engine;
+#[cfg(test)]
+mod tests;
+
+pub use engine::{FromValue, Lox, LoxError, ToValue};
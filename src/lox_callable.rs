@@ -1,50 +1,83 @@
-use std::{collections::HashMap};
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 
-use crate::{enviroment::create_enviroment, interpreter::{self, Interpreter, RuntimeError, Value}, lox_instance::LoxInstance, stmt::Stmt};
+use crate::{enviroment::{create_enviroment, EnviromentRef}, interpreter::{self, Interpreter, RuntimeError, Value}, lox_instance::LoxInstance, lox_native::NativeFunction, stmt::Stmt};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum LoxCallable {
     LoxFunction(LoxFunction),
-    LoxClass(LoxClass)
+    LoxClass(Rc<LoxClass>),
+    NativeFunction(NativeFunction)
 }
 
 pub trait Callable {
     fn arity(self) -> usize;
-    fn call_function(self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Value;
+    fn call_function(self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError>;
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Clone)]
 
 pub struct LoxFunction {
-    pub stmt: Stmt
+    //Shared by every closure and bound method made from the same declaration
+    pub stmt: Rc<Stmt>,
+    //The environment the function was declared in, calls run in a new environment inside it
+    pub closure: EnviromentRef,
+    //init methods return the instance whatever their body does
+    pub is_initializer: bool
+}
+
+//The same declaration closing over the same environment
+impl PartialEq for LoxFunction {
+    fn eq(&self, other: &Self) -> bool {
+        return self.stmt == other.stmt && Rc::ptr_eq(&self.closure, &other.closure);
+    }
+}
+
+impl LoxFunction {
+    //A copy of the method whose closure defines 'this' as the instance it was looked up on
+    pub fn bind(&self, instance: Rc<RefCell<LoxInstance>>) -> LoxFunction {
+        let enviroment = create_enviroment(Some(self.closure.clone()));
+        enviroment.borrow_mut().put(format!("this"), Value::LoxInstance(instance));
+        return LoxFunction { stmt: self.stmt.clone(), closure: enviroment, is_initializer: self.is_initializer };
+    }
+}
+
+//The closure can hold the function itself, so it isn't printed
+impl fmt::Debug for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.stmt.as_ref() {
+            Stmt::Function(name, _, _) => write!(f, "<fn {}>", name.lexeme),
+            _ => write!(f, "<fn>")
+        }
+    }
 }
 impl Callable for LoxFunction {
-    fn call_function(self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Value {
-        let env = interpreter.enviroment.clone();
-        let mut enviroment = create_enviroment(Some(interpreter.global.clone()));
+    fn call_function(self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let enviroment = create_enviroment(Some(self.closure.clone()));
+        let mut return_value = Value::Nil;
 
-        if let Stmt::Function(name, params, body) = self.stmt {
+        if let Stmt::Function(name, params, body) = self.stmt.as_ref() {
             for i in 0..params.len() {
-                enviroment.put(params[i].lexeme.clone(), arguments[i].clone())
+                enviroment.borrow_mut().put(params[i].lexeme.clone(), arguments[i].clone())
             }
-            if let Err(error_return) = interpreter.interpret_statement_block(body,enviroment) {
-                if let RuntimeError::Return(return_value_option) = error_return {
-                    if let Some(return_value) = return_value_option {
-                        interpreter.enviroment = env;
-                        return return_value
-                    }   
-                }
+            //A return unwinds the body like an error, anything else stops the script
+            match interpreter.interpret_statement_block(body.clone(),enviroment) {
+                Ok(()) | Err(RuntimeError::Return(None)) => (),
+                Err(RuntimeError::Return(Some(value))) => return_value = value,
+                Err(error) => return Err(error)
             }
 
         } else {
             panic!("Interpreter has failed to enforce type checking on statements.")
         }
-        interpreter.enviroment = env;
-        return Value::Nil
+        //Also when init is called again on an existing instance or left with a bare return
+        if self.is_initializer {
+            return Ok(self.closure.borrow().values.get("this").cloned().unwrap_or(Value::Nil));
+        }
+        return Ok(return_value)
         
     }
     fn arity(self) -> usize {
-        if let Stmt::Function(name, params, body) = self.stmt {
+        if let Stmt::Function(name, params, body) = self.stmt.as_ref() {
             return params.len()
         } else {
             panic!("Interpreter has failed to enforce type checking on statements.")
@@ -53,44 +86,50 @@ impl Callable for LoxFunction {
 }
 
 
-#[derive(Debug, PartialEq, Clone)]
-
+#[derive(Debug)]
 pub struct LoxClass {
     pub name: String,
-    pub methods: HashMap<String, LoxFunction>,
-    pub superclass: Box<Option<LoxClass>>
+    pub methods: HashMap<String, Rc<LoxFunction>>,
+    pub superclass: Option<Rc<LoxClass>>
+}
+
+//Every instance and subclass shares its class, so a class is only ever equal to itself
+impl PartialEq for LoxClass {
+    fn eq(&self, other: &Self) -> bool {
+        return std::ptr::eq(self, other);
+    }
 }
 
-impl Callable for LoxClass {
-    fn call_function(self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Value {
-        let instance = LoxInstance {
-            class: self.clone(),
+impl Callable for Rc<LoxClass> {
+    fn call_function(self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let initalizer = self.find_method("init");
+        let instance = Rc::new(RefCell::new(LoxInstance {
+            class: self,
             fields: HashMap::new(),
-        };
+        }));
 
-        let initalizer = self.find_method("init".to_string());
         if let Some(initalizer_func) = initalizer {
-            initalizer_func.call_function(interpreter, arguments);
+            initalizer_func.bind(instance.clone()).call_function(interpreter, arguments)?;
         }
-        return Value::LoxInstance(instance);
+        return Ok(Value::LoxInstance(instance));
     }
     fn arity(self) -> usize {
-        let initalizer = self.find_method("init".to_string());
+        let initalizer = self.find_method("init");
         if let Some(initalizer_func) = initalizer {
-            return initalizer_func.arity()
+            return initalizer_func.as_ref().clone().arity()
         }
         return 0
     }
 }
 
 impl LoxClass {
-    pub fn find_method(self, name: String) ->Option<LoxFunction> {
-        if let Some(method) = self.methods.get(&name) {
+    pub fn find_method(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        if let Some(method) = self.methods.get(name) {
             return Some(method.clone())
         }
 
-        if let Some(superclass_exists) = *self.superclass {
-            return superclass_exists.find_method(name)
+        if let Some(superclass) = &self.superclass {
+            return superclass.find_method(name)
         }
         return None
     }
@@ -1,29 +1,39 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 
-use crate::{expr::{Expr, Literal}, interpreter::{RuntimeError, Value}, lox_callable::{LoxCallable, LoxClass, LoxFunction}, scanner::{Token, TokenType}, stmt::Stmt};
+use crate::{interpreter::{RuntimeError, Value}, lox_callable::{LoxCallable, LoxClass}, scanner::Token};
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(Clone)]
 
 pub struct LoxInstance {
-    pub class: LoxClass,
+    pub class: Rc<LoxClass>,
     pub fields: HashMap<String, Value>,
 }
 
+//Fields can refer back to the instance, so neither comparing nor printing looks inside them
+impl PartialEq for LoxInstance {
+    fn eq(&self, other: &Self) -> bool {
+        return std::ptr::eq(self, other);
+    }
+}
+
+impl fmt::Debug for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} instance", self.class.name)
+    }
+}
+
 impl LoxInstance {
-    pub fn get(self, name: Token) -> Result<Value, RuntimeError> {
-        if self.fields.contains_key(&name.lexeme) {
-            return Ok(self.fields.get(&name.lexeme).unwrap().clone());
+    //Takes the shared instance so methods see the same object the caller has
+    pub fn get(instance: &Rc<RefCell<LoxInstance>>, name: Token) -> Result<Value, RuntimeError> {
+        if let Some(value) = instance.borrow().fields.get(&name.lexeme) {
+            return Ok(value.clone());
         }
 
-        if let Some(method) = self.clone().class.find_method(name.lexeme.clone()) {
-            if let Stmt::Function(name,params ,mut code ) = method.stmt {
-                let added_this = Stmt::Var(Token { token_type: TokenType::THIS, lexeme: "this".to_string(), literal: None, line: name.line }, Expr::This(Value::LoxInstance(self.clone())));
-                code.insert(0, added_this);
-                let func_stmt = Stmt::Function(name, params, code);
-                return Ok(Value::LoxCallable(Box::new(LoxCallable::LoxFunction( LoxFunction {stmt: func_stmt}))));
-            }
+        let method = instance.borrow().class.find_method(&name.lexeme);
+        if let Some(method) = method {
+            return Ok(Value::LoxCallable(Box::new(LoxCallable::LoxFunction(method.bind(instance.clone())))));
         }
-        return Err(RuntimeError::Class(format!("Undefined property {}.", name.lexeme)))
+        return Err(RuntimeError::Variable(name.clone(), format!("Undefined property '{}'.", name.lexeme)))
         
     }
 
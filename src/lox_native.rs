@@ -0,0 +1,20 @@
+use crate::{interpreter::Interpreter, native::{self, Arity, Builtin}};
+
+#[derive(Debug, Clone)]
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: Arity,
+    pub function: Builtin<Interpreter>
+}
+
+//Natives are only ever defined once under their name
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        return self.name == other.name;
+    }
+}
+
+//Defined as globals in every new interpreter, the same set the VM has
+pub fn stdlib() -> Vec<NativeFunction> {
+    return native::stdlib().into_iter().map(|(name, arity, function)| NativeFunction { name: name.to_string(), arity, function }).collect();
+}
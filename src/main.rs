@@ -1,73 +1,199 @@
-use std::default;
 use std::env;
 use std::fs;
+use std::process;
 
-use bytecode::Chunk;
-use bytecode::Value;
-use bytecode::OpCode;
-use compiler::compile;
-use debug::disassemble_chunk;
-use virtual_machine::interpret_vm;
-use virtual_machine::VirtualMachine;
+use lox::bench::{bench, format_json, format_table};
+use lox::repl;
+use lox::runner::{render, report, run, Backend, Options};
+use lox::serialize::compile_to_bytecode;
+use lox::virtual_machine::{interpret_bytecode, InterpretResult};
 
-use crate::interpreter::interpret;
-mod scanner;
-mod expr;
-mod parser;
-mod interpreter;
-mod stmt;
-mod enviroment;
-mod lox_callable;
-mod lox_instance;
-mod bytecode;
-mod debug;
-mod virtual_machine;
-mod compiler;
-mod tests;
+//Exit codes follow clox, which takes them from sysexits.h
+const EXIT_USAGE: i32 = 64;
+const EXIT_COMPILE_ERROR: i32 = 65;
+const EXIT_RUNTIME_ERROR: i32 = 70;
+const EXIT_IO_ERROR: i32 = 74;
+
+const USAGE: &str = "Usage: lox [--backend tree|vm] [--disassemble] [--trace]
+       lox run [--backend tree|vm] [--disassemble] [--trace] <file>
+       lox compile <file.lox> [-o <file.loxc>]
+       lox bench [--backend tree|vm] [--iterations <n>] [--json] [<file.lox>...]";
+
+//Where lox bench looks for programs when it isn't given any
+const BENCH_DIR: &str = "bench";
+const BENCH_ITERATIONS: usize = 5;
 
 fn main() {
-    let arg: Vec<String> = env::args().collect();
-    let file;
-    print!("{:?}",arg);
-    print!("t");
-    if arg.len() == 2 {
-        //run prompt left for now
-        file = arg[1].clone();
-    } else {
-        file = String::from("src/test.lox");
-        //run_file(&file);
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("compile") => compile_file(&args[1..]),
+        Some("bench") => bench_files(&args[1..]),
+        Some("run") => {
+            let (options, files) = parse_options(&args[1..]);
+            match files.as_slice() {
+                [file] => run_file(file, &options),
+                _ => usage_error()
+            }
+        },
+        _ => {
+            let (options, files) = parse_options(&args);
+            if !files.is_empty() {
+                usage_error();
+            }
+            repl::run_prompt(&options);
+        }
     }
-    //let file = String::from("src/test.lox");
-    let contents = fs::read_to_string(file)
-        .expect("Error: file doesnt exist");
-    
-    interpret_vm(contents, true);
-        
-    }
-    // let chunk = Chunk {
-    //     code: vec![
-    //     (OpCode::Constant(0), 123),
-    //     (OpCode::Constant(1), 123),
-    //     (OpCode::Add, 123),
-    //     (OpCode::Constant(2), 123),
-    //     (OpCode::Divide, 123),
-    //      (OpCode::Negate,123),  
-    //      (OpCode::Return,123)],
-    //     constant: vec![Constant::Number(1.2), Constant::Number(3.4), Constant::Number(5.6)]
-    // };
+}
 
-    
+fn usage_error() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(EXIT_USAGE);
+}
 
+//Splits the flags from the remaining positional arguments
+fn parse_options(args: &[String]) -> (Options, Vec<String>) {
+    let mut options = Options::default();
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => {
+                options.backend = match args.next().map(|backend| backend.as_str()) {
+                    Some("tree") => Backend::Tree,
+                    Some("vm") => Backend::Vm,
+                    _ => usage_error()
+                }
+            },
+            "--disassemble" => options.disassemble = true,
+            "--trace" => options.trace = true,
+            flag if flag.starts_with("--") => usage_error(),
+            _ => positional.push(arg.clone())
+        }
+    }
+    if options.backend == Backend::Tree && (options.disassemble || options.trace) {
+        eprintln!("Error: the tree-walk backend has no bytecode to disassemble or trace.");
+        process::exit(EXIT_USAGE);
+    }
+    return (options, positional);
+}
+
+fn run_file(file: &String, options: &Options) {
+    let result = if file.ends_with(".loxc") {
+        if options.backend == Backend::Tree {
+            eprintln!("Error: the tree-walk backend can only run source files.");
+            process::exit(EXIT_USAGE);
+        }
+        let bytes = match fs::read(file) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                eprintln!("Error: could not read {}: {}", file, error);
+                process::exit(EXIT_IO_ERROR);
+            }
+        };
+        report(interpret_bytecode(&bytes, options.disassemble, options.trace), "")
+    } else {
+        let contents = match fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(error) => {
+                eprintln!("Error: could not read {}: {}", file, error);
+                process::exit(EXIT_IO_ERROR);
+            }
+        };
+        run(contents, options)
+    };
 
+    match result {
+        InterpretResult::InterpretOk => (),
+        InterpretResult::InterpretCompilerError => process::exit(EXIT_COMPILE_ERROR),
+        InterpretResult::InterpretRuntimeError => process::exit(EXIT_RUNTIME_ERROR),
+        InterpretResult::InterpretExit(code) => process::exit(code)
+    }
+}
 
-fn run_file(file_path: &String) {
+//lox compile <file> [-o <output>], the output defaults to the source path with a .loxc extension
+fn compile_file(args: &[String]) {
+    let (source_path, output_path) = match args {
+        [source] => (source.clone(), std::path::Path::new(source).with_extension("loxc").to_string_lossy().to_string()),
+        [source, flag, output] if flag == "-o" => (source.clone(), output.clone()),
+        _ => usage_error()
+    };
 
+    let contents = match fs::read_to_string(&source_path) {
+        Ok(contents) => contents,
+        Err(error) => {
+            eprintln!("Error: could not read {}: {}", source_path, error);
+            process::exit(EXIT_IO_ERROR);
+        }
+    };
+    match compile_to_bytecode(contents.clone()) {
+        Ok(bytes) => {
+            if let Err(error) = fs::write(&output_path, bytes) {
+                eprintln!("Error: could not write {}: {}", output_path, error);
+                process::exit(EXIT_IO_ERROR);
+            }
+        },
+        Err(diagnostics) => {
+            render(&diagnostics, &contents);
+            process::exit(EXIT_COMPILE_ERROR);
+        }
+    }
 }
 
-// fn run_file_treewalk(file_path: &String) {
-    
-    
-//     let scanner = scanner::scan(contents);
-//     let parser = parser::parse(scanner.tokens);
-//     let _interpreter = interpret(parser);
-// }
+//lox bench [--iterations <n>] [--json] [<file>...], runs every .lox file in bench/ when no files are given
+fn bench_files(args: &[String]) {
+    let mut iterations = BENCH_ITERATIONS;
+    let mut json = false;
+    let mut rest = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--iterations" => {
+                iterations = match args.next().and_then(|count| count.parse().ok()) {
+                    Some(count) if count > 0 => count,
+                    _ => usage_error()
+                }
+            },
+            "--json" => json = true,
+            _ => rest.push(arg.clone())
+        }
+    }
+    let (options, mut files) = parse_options(&rest);
+    if files.is_empty() {
+        files = match fs::read_dir(BENCH_DIR) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| path.extension().is_some_and(|extension| extension == "lox")).map(|path| path.to_string_lossy().to_string()).collect(),
+            Err(error) => {
+                eprintln!("Error: could not read {}: {}", BENCH_DIR, error);
+                process::exit(EXIT_IO_ERROR);
+            }
+        };
+        files.sort();
+    }
+
+    let mut results = vec![];
+    for file in files.iter() {
+        let contents = match fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(error) => {
+                eprintln!("Error: could not read {}: {}", file, error);
+                process::exit(EXIT_IO_ERROR);
+            }
+        };
+        let name = std::path::Path::new(file).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or(file.clone());
+        match bench(&name, &contents, options.backend, iterations) {
+            Ok(result) => results.push(result),
+            Err(errors) => {
+                eprintln!("Error: {} failed", file);
+                for line in errors {
+                    eprintln!("{}", line);
+                }
+                process::exit(EXIT_RUNTIME_ERROR);
+            }
+        }
+    }
+
+    if json {
+        print!("{}", format_json(&results, options.backend, iterations));
+    } else {
+        print!("{}", format_table(&results));
+    }
+}
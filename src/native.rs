@@ -0,0 +1,150 @@
+use std::{io::{self, BufRead}, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{bytecode::Value, heap::Heap, output::OutputSink, virtual_machine::{RuntimeError, VirtualMachine}};
+
+//How many arguments a native function takes, variadic natives check their own arguments
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arity {
+    Fixed(usize),
+    Variadic
+}
+
+impl Arity {
+    pub fn check(&self, arg_count: usize) -> Result<(), String> {
+        match self {
+            Arity::Fixed(arity) if *arity != arg_count => Err(format!("Expected {} arguments but got {}.", arity, arg_count)),
+            _ => Ok(())
+        }
+    }
+}
+
+//What the standard library needs from a backend. Both backends implement it so they run the same natives
+pub trait Natives {
+    type Value;
+    fn nil(&self) -> Self::Value;
+    fn number(&self, n: f64) -> Self::Value;
+    fn string(&mut self, string: String) -> Self::Value;
+    fn as_number(&self, value: &Self::Value) -> Option<f64>;
+    fn as_string<'a>(&'a self, value: &'a Self::Value) -> Option<&'a str>;
+    //The same text print shows
+    fn format(&self, value: &Self::Value) -> String;
+    fn type_name(&self, value: &Self::Value) -> &'static str;
+    fn output(&mut self) -> &mut dyn OutputSink;
+}
+
+//Natives don't know where they were called from, each backend turns this into a runtime error at the call
+pub enum NativeError {
+    Message(String),
+    Exit(i32)
+}
+
+pub type Builtin<N> = fn(&mut N, &[<N as Natives>::Value]) -> Result<<N as Natives>::Value, NativeError>;
+
+//Defined as globals by both backends
+pub fn stdlib<N: Natives>() -> [(&'static str, Arity, Builtin<N>); 7] {
+    return [
+        ("clock", Arity::Fixed(0), native_clock),
+        ("str", Arity::Fixed(1), native_str),
+        ("num", Arity::Fixed(1), native_num),
+        ("len", Arity::Fixed(1), native_len),
+        ("type", Arity::Fixed(1), native_type),
+        ("input", Arity::Variadic, native_input),
+        ("exit", Arity::Variadic, native_exit),
+    ];
+}
+
+//Natives a host defines get the heap so they can read and create strings
+pub type NativeFn = fn(&mut Heap, &[Value]) -> Result<Value, RuntimeError>;
+
+#[derive(Clone, Copy)]
+pub enum NativeCode {
+    Stdlib(Builtin<VirtualMachine>),
+    Host(NativeFn)
+}
+
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: Arity,
+    pub function: NativeCode
+}
+
+fn error<V>(message: String) -> Result<V, NativeError> {
+    return Err(NativeError::Message(message));
+}
+
+fn native_clock<N: Natives>(natives: &mut N, _args: &[N::Value]) -> Result<N::Value, NativeError> {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs_f64()).unwrap_or(0.0);
+    return Ok(natives.number(seconds));
+}
+
+fn native_str<N: Natives>(natives: &mut N, args: &[N::Value]) -> Result<N::Value, NativeError> {
+    let string = natives.format(&args[0]);
+    return Ok(natives.string(string));
+}
+
+fn native_num<N: Natives>(natives: &mut N, args: &[N::Value]) -> Result<N::Value, NativeError> {
+    if let Some(n) = natives.as_number(&args[0]) {
+        return Ok(natives.number(n));
+    }
+    match natives.as_string(&args[0]) {
+        Some(string) => match string.trim().parse::<f64>().ok().filter(|n| n.is_finite()) {
+            Some(n) => Ok(natives.number(n)),
+            None => error(format!("Can't convert '{}' to a number.", string))
+        },
+        None => error(format!("num() expects a string or a number."))
+    }
+}
+
+fn native_len<N: Natives>(natives: &mut N, args: &[N::Value]) -> Result<N::Value, NativeError> {
+    match natives.as_string(&args[0]) {
+        Some(string) => Ok(natives.number(string.chars().count() as f64)),
+        None => error(format!("len() expects a string."))
+    }
+}
+
+fn native_type<N: Natives>(natives: &mut N, args: &[N::Value]) -> Result<N::Value, NativeError> {
+    let name = natives.type_name(&args[0]);
+    return Ok(natives.string(format!("{}", name)));
+}
+
+//input() or input(prompt), returns nil once stdin is closed
+fn native_input<N: Natives>(natives: &mut N, args: &[N::Value]) -> Result<N::Value, NativeError> {
+    match args {
+        [] => (),
+        [prompt] => {
+            let prompt = natives.format(prompt);
+            natives.output().write_prompt(&prompt);
+        },
+        _ => return error(format!("Expected at most 1 argument but got {}.", args.len()))
+    }
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => Ok(natives.nil()),
+        Ok(_) => Ok(natives.string(line.trim_end_matches(['\n', '\r']).to_string()))
+    }
+}
+
+//exit() or exit(code), stops the script without it counting as a runtime error
+fn native_exit<N: Natives>(natives: &mut N, args: &[N::Value]) -> Result<N::Value, NativeError> {
+    match args {
+        [] => Err(NativeError::Exit(0)),
+        [code] => match natives.as_number(code) {
+            Some(code) => Err(NativeError::Exit(code as i32)),
+            None => error(format!("exit() expects a number."))
+        },
+        _ => error(format!("Expected at most 1 argument but got {}.", args.len()))
+    }
+}
+
+//What type() returns for a value
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Number(_) => "number",
+        Value::Bool(_) => "bool",
+        Value::Nil => "nil",
+        Value::String(_) => "string",
+        Value::Function(_) | Value::Closure(_) | Value::BoundMethod(_) | Value::Native(_) => "function",
+        Value::Class(_) => "class",
+        Value::Instance(_) => "instance"
+    }
+}
@@ -0,0 +1,69 @@
+use std::{cell::RefCell, io::{self, Write}, rc::Rc};
+
+//Where print statements send their text. Both backends write to it as soon as a value is printed
+pub trait OutputSink {
+    //Called once per print statement, without the newline
+    fn write_line(&mut self, line: &str);
+
+    //Called by input() before it reads a line. Sinks that only deal in lines get the prompt as a line of its own
+    fn write_prompt(&mut self, prompt: &str) {
+        self.write_line(prompt);
+    }
+}
+
+//The default sink
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn write_line(&mut self, line: &str) {
+        println!("{}", line);
+    }
+
+    //Stays on the same line as what the user types
+    fn write_prompt(&mut self, prompt: &str) {
+        print!("{}", prompt);
+        let _ = io::stdout().flush();
+    }
+}
+
+//Sends output to anything implementing io::Write, such as a file
+pub struct WriteSink<W: Write>(pub W);
+
+impl<W: Write> OutputSink for WriteSink<W> {
+    fn write_line(&mut self, line: &str) {
+        //Like print in clox a failed write doesn't stop the script
+        let _ = writeln!(self.0, "{}", line);
+    }
+
+    fn write_prompt(&mut self, prompt: &str) {
+        let _ = write!(self.0, "{}", prompt);
+        let _ = self.0.flush();
+    }
+}
+
+//Any closure taking the printed line works as a callback sink
+impl<F: FnMut(&str)> OutputSink for F {
+    fn write_line(&mut self, line: &str) {
+        self(line);
+    }
+}
+
+//Collects lines in memory. Clones share the same lines so one can be given to an interpreter and the other read afterwards
+#[derive(Clone, Default)]
+pub struct OutputBuffer(Rc<RefCell<Vec<String>>>);
+
+impl OutputBuffer {
+    pub fn lines(&self) -> Vec<String> {
+        return self.0.borrow().clone();
+    }
+
+    pub fn take(&self) -> Vec<String> {
+        return std::mem::take(&mut self.0.borrow_mut());
+    }
+}
+
+impl OutputSink for OutputBuffer {
+    fn write_line(&mut self, line: &str) {
+        self.0.borrow_mut().push(line.to_string());
+    }
+}
@@ -1,37 +1,75 @@
 use crate::expr::{Expr, Literal};
+use crate::diagnostics::{Diagnostic, ErrorCode};
 use crate::scanner::{Token, TokenType, self, Scanner};
 use crate::stmt::Stmt;
 
 struct Parser {
     tokens: Vec<Token>,
-    current: usize
+    current: usize,
+    //REPL input may leave out the ';' after its last expression
+    repl: bool,
+    diagnostics: Vec<Diagnostic>
 }
 enum ParseError {
     Default,
     Assignment
 }
 
-pub fn parse(tokens: Vec<Token>) -> Vec<Stmt> {
+//Returns every syntax error found if there were any
+pub fn parse(tokens: Vec<Token>) -> Result<Vec<Stmt>, Vec<Diagnostic>> {
+    return parse_tokens(tokens, false);
+}
+
+//Parses REPL input, top level expression statements become print statements
+pub fn parse_repl(tokens: Vec<Token>) -> Result<Vec<Stmt>, Vec<Diagnostic>> {
+    return parse_tokens(tokens, true);
+}
+
+fn parse_tokens(tokens: Vec<Token>, repl: bool) -> Result<Vec<Stmt>, Vec<Diagnostic>> {
     let mut statements: Vec<Stmt> = vec![];
+    let mut had_error = false;
+    //The scanner leaves error tokens in the list, they are reported here and skipped like the compiler does
+    let (error_tokens, tokens): (Vec<Token>, Vec<Token>) = tokens.into_iter().partition(|token| token.token_type == TokenType::TOKEN_ERROR);
     let mut parser = Parser {
         tokens,
-        current: 0
+        current: 0,
+        repl,
+        diagnostics: vec![]
     };
+    for token in error_tokens {
+        if let Some(scanner::Literal::Str(message)) = &token.literal {
+            let (code, message) = (token.error_code(), message.clone());
+            parser.error(token, code, &message);
+        }
+    }
     while !parser.is_at_end() {
         if let Ok(dec) = parser.declaration() {
-            statements.push(dec);
+            match dec {
+                Stmt::Expression(expr) if repl => statements.push(Stmt::Print(expr)),
+                _ => statements.push(dec)
+            }
         } else {
+            had_error = true;
             parser.synchronize();
         }
     }
-    return statements
+    if had_error || !parser.diagnostics.is_empty() {
+        parser.diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+        return Err(parser.diagnostics)
+    }
+    return Ok(statements)
 }
 
 impl Parser {
     fn declaration(&mut self) -> Result<Stmt, ParseError> {
         if self.token_match(vec![TokenType::VAR]) {
             return self.var_declaration();
+        } else if self.token_match(vec![TokenType::FUN]) {
+            return self.function_statement("function");
+        } else if self.token_match(vec![TokenType::CLASS]) {
+            return self.class_statement();
         }
+        //Declarations can't be the body of an if or a loop, so those only parse statements
         return self.statement();
     }
 
@@ -49,11 +87,7 @@ impl Parser {
     }
 
     fn statement(&mut self) -> Result<Stmt, ParseError> {
-        if self.token_match(vec![TokenType::FUN]) {
-            self.function_statement("function")   
-        } else if self.token_match(vec![TokenType::CLASS]) {
-            return self.class_statement();
-        } else if self.token_match(vec![TokenType::PRINT]) {
+        if self.token_match(vec![TokenType::PRINT]) {
             return self.print_statement();
         } else if self.token_match(vec![TokenType::RETURN]) {
             return self.return_statement(); 
@@ -71,12 +105,12 @@ impl Parser {
     }
 
     fn class_statement(&mut self) -> Result<Stmt, ParseError> {
-        let name = self.consume(TokenType::IDENTIFIER, "Except class name.")?.clone();
+        let name = self.consume(TokenType::IDENTIFIER, "Expect class name.")?.clone();
         
         let mut superclass: Option<Expr> = None;
         if self.token_match(vec![TokenType::LESS]) {
             self.consume(TokenType::IDENTIFIER, "Expect superclass name.");
-            superclass = Some(Expr::Variable(self.previous().clone()));
+            superclass = Some(Expr::Variable(self.previous().clone(), None));
         }
         
         self.consume(TokenType::LEFT_BRACE, "Expect '{' before class body.")?;
@@ -100,7 +134,8 @@ impl Parser {
             params.push(self.consume(TokenType::IDENTIFIER, "Expect parameter name.")?.clone());
             while self.token_match(vec![TokenType::COMMA]) {
                 if params.len() >= 255 {
-                    self.error_message(self.peek(), "Can't have more than 255 parameters");
+                    let token = self.peek().clone();
+                    self.error(token, ErrorCode::LimitExceeded, "Can't have more than 255 parameters.");
                 }
                 params.push(self.consume(TokenType::IDENTIFIER, "Expect parameter name.")?.clone());
             }
@@ -146,13 +181,13 @@ impl Parser {
         }
 
         let mut condition = Expr::Literal(Literal::True);
-        if !self.token_match(vec![TokenType::SEMICOLON]) {
+        if !self.check(TokenType::SEMICOLON) {
             condition = self.expression()?;
         } 
         self.consume(TokenType::SEMICOLON, "Expect ';' after loop condition.")?;
 
         let mut increment: Option<Expr> = None;
-        if !self.token_match(vec![TokenType::RIGHT_PAREN]) {
+        if !self.check(TokenType::RIGHT_PAREN) {
             increment = Some(self.expression()?);
         } 
         self.consume(TokenType::RIGHT_PAREN, "Expect ')' after clauses.")?;
@@ -197,14 +232,16 @@ impl Parser {
             statements.push(self.declaration()?);
         }
 
-        self.consume(TokenType::RIGHT_BRACE, "Except '}' after block.")?;
+        self.consume(TokenType::RIGHT_BRACE, "Expect '}' after block.")?;
         return Ok(statements);
     }
 
 
     fn expression_statement(&mut self) -> Result<Stmt, ParseError> {
         let expr = self.expression()?;
-        self.consume(TokenType::SEMICOLON, "Expect ';' after expression.")?;
+        if !(self.repl && self.is_at_end()) {
+            self.consume(TokenType::SEMICOLON, "Expect ';' after expression.")?;
+        }
         return Ok(Stmt::Expression(expr));
     }
 
@@ -215,14 +252,15 @@ impl Parser {
     fn assingment(&mut self) -> Result<Expr, ParseError> {
         let expr = self.or()?;
         if self.token_match(vec![TokenType::EQUAL]) {
-            let equals = self.previous();
+            let equals = self.previous().clone();
             let value = self.assingment()?;
 
             match expr {
-                Expr::Variable(token) => return Ok(Expr::Assign(token, Box::new(value))),
+                Expr::Variable(token, _) => return Ok(Expr::Assign(token, None, Box::new(value))),
                 Expr::Get(expr, token ) => return Ok(Expr::Set(expr, token, Box::new(value))),
                 _ => {
-                    print!("Invalid assignment target");
+                    //Reported without unwinding, the parser isn't confused about where it is
+                    self.error(equals, ErrorCode::InvalidAssignmentTarget, "Invalid assignment target.");
                 }
             }
         }
@@ -335,7 +373,8 @@ impl Parser {
             arguments.push(self.expression()?);
             while self.token_match(vec![TokenType::COMMA]) {
                 if arguments.len() >= 255 {
-                    self.error_message(self.peek(), "Cant have more than 255 arguments");
+                    let token = self.peek().clone();
+                    self.error(token, ErrorCode::LimitExceeded, "Can't have more than 255 arguments.");
                 }
                 arguments.push(self.expression()?);
             }
@@ -366,16 +405,26 @@ impl Parser {
                 None => println!("Literal error")
             }
         }
+        if self.token_match(vec![TokenType::SUPER]) {
+            let keyword = self.previous().clone();
+            self.consume(TokenType::DOT, "Expect '.' after 'super'.")?;
+            let method = self.consume(TokenType::IDENTIFIER, "Expect superclass method name.")?.clone();
+            return Ok(Expr::Super(keyword, method, None))
+        }
+        if self.token_match(vec![TokenType::THIS]) {
+            return Ok(Expr::This(self.previous().clone(), None))
+        }
         if self.token_match(vec![TokenType::IDENTIFIER]) {
-            return Ok(Expr::Variable(self.previous().clone()))
+            return Ok(Expr::Variable(self.previous().clone(), None))
         }
         if self.token_match(vec![TokenType::LEFT_PAREN]) {
             let expr = Box::new(self.expression()?); 
             self.consume(TokenType::RIGHT_PAREN, "Expect ')' after expression.")?;
             return Ok(Expr::Grouping(expr)); 
         }
-        //TODO: make this something betterS
-        return Ok(Expr::Literal(Literal::Nil))
+        let token = self.peek().clone();
+        self.error(token, ErrorCode::ExpectExpression, "Expect expression.");
+        return Err(ParseError::Default)
 
     }
 
@@ -420,18 +469,14 @@ impl Parser {
         if self.check(token_type) {
             return Ok(self.advance())
         }
-        let problem_token = self.peek();
-        self.error_message(problem_token, message);
+        let problem_token = self.peek().clone();
+        self.error(problem_token, ErrorCode::ExpectToken, message);
         Err(ParseError::Default)
 
     }
 
-    fn error_message(&self, token: &Token, message: &str) {
-        if token.token_type == TokenType::EOF {
-            println!("Line {} at end {}", token.line, message)
-        } else {
-            println!("Line {} at '{}' {}", token.line, token.lexeme, message);
-        }
+    fn error(&mut self, token: Token, code: ErrorCode, message: &str) {
+        self.diagnostics.push(Diagnostic::at(&token, code, message.to_string()));
     }
 
 
@@ -0,0 +1,113 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use crate::{interpreter::Interpreter, runner::{report, run_tree_walk, Backend, Options}, virtual_machine::{InterpretResult, VirtualMachine}};
+
+const HELP: &str = "Commands: :load <file.lox>, :reset, :disasm";
+
+//Interpreter state kept alive between inputs so globals defined on one line can be used on the next
+enum Session {
+    Vm(VirtualMachine),
+    Tree(Interpreter)
+}
+
+impl Session {
+    fn new(backend: Backend) -> Session {
+        match backend {
+            Backend::Vm => Session::Vm(VirtualMachine::new()),
+            Backend::Tree => Session::Tree(Interpreter::new())
+        }
+    }
+
+    //repl input prints the value of top level expressions, loaded files run as they would from the command line
+    fn run(&mut self, source: String, repl: bool, disassemble: bool, trace: bool) {
+        let result = match self {
+            Session::Vm(vm) => {
+                let output = if repl {
+                    vm.interpret_repl(source.clone(), disassemble, trace)
+                } else {
+                    vm.interpret_source(source.clone(), disassemble, trace)
+                };
+                report(output, &source)
+            },
+            Session::Tree(interpreter) => run_tree_walk(interpreter, source, repl)
+        };
+        if let InterpretResult::InterpretExit(code) = result {
+            process::exit(code);
+        }
+    }
+}
+
+pub fn run_prompt(options: &Options) {
+    let mut session = Session::new(options.backend);
+    let mut disassemble = options.disassemble;
+    let mut input = String::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("{}", if input.is_empty() { "> " } else { "... " });
+        io::stdout().flush().unwrap();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => {
+                println!();
+                return;
+            }
+        };
+
+        if input.is_empty() && line.trim_start().starts_with(':') {
+            let mut command = line.trim().splitn(2, char::is_whitespace);
+            match (command.next().unwrap(), command.next().map(str::trim)) {
+                (":load", Some(file)) => match fs::read_to_string(file) {
+                    Ok(contents) => session.run(contents, false, disassemble, options.trace),
+                    Err(error) => eprintln!("Error: could not read {}: {}", file, error)
+                },
+                (":reset", None) => session = Session::new(options.backend),
+                (":disasm", None) => {
+                    if options.backend == Backend::Tree {
+                        eprintln!("The tree-walk backend has no bytecode to disassemble.");
+                    } else {
+                        disassemble = !disassemble;
+                        println!("Disassembly {}.", if disassemble { "on" } else { "off" });
+                    }
+                },
+                _ => eprintln!("Unknown command '{}'. {}", line.trim(), HELP)
+            }
+            continue;
+        }
+
+        input.push_str(&line);
+        input.push('\n');
+        if is_incomplete(&input) {
+            continue;
+        }
+        session.run(std::mem::take(&mut input), true, disassemble, options.trace);
+    }
+}
+
+//True while a '{' or '(' is left open or a string is unterminated, so the prompt keeps reading lines.
+//Extra closing brackets are left for the parser to report
+pub fn is_incomplete(source: &str) -> bool {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_string {
+            if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '(' => depth += 1,
+            '}' | ')' => depth -= 1,
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            },
+            _ => ()
+        }
+    }
+    return in_string || depth > 0;
+}
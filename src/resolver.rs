@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use crate::{diagnostics::{Diagnostic, ErrorCode}, expr::Expr, scanner::{Token, TokenType}, stmt::Stmt};
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Initializer,
+    Method
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass
+}
+
+//A name declared in a scope, with the token that declared it for the note on a duplicate declaration
+struct Variable {
+    name: Token,
+    //False until its initializer has been resolved
+    defined: bool
+}
+
+struct Resolver {
+    //Innermost scope last
+    scopes: Vec<HashMap<String, Variable>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    diagnostics: Vec<Diagnostic>
+}
+
+//Fills in how many scopes up each local variable lives before the tree-walker runs, globals are left as None
+pub fn resolve(statements: &mut [Stmt]) -> Result<(), Vec<Diagnostic>> {
+    let mut resolver = Resolver {
+        scopes: vec![],
+        current_function: FunctionType::None,
+        current_class: ClassType::None,
+        diagnostics: vec![]
+    };
+    resolver.resolve_statements(statements);
+    if resolver.diagnostics.is_empty() {
+        return Ok(());
+    }
+    return Err(resolver.diagnostics);
+}
+
+impl Resolver {
+    fn resolve_statements(&mut self, statements: &mut [Stmt]) {
+        for statement in statements.iter_mut() {
+            self.resolve_statement(statement);
+        }
+    }
+
+    fn resolve_statement(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Block(statements) => {
+                self.begin_scope();
+                self.resolve_statements(statements);
+                self.end_scope();
+            },
+            Stmt::Class(name, superclass, methods) => self.resolve_class(name, superclass, methods),
+            Stmt::Expression(expr) | Stmt::Print(expr) => self.resolve_expression(expr),
+            Stmt::Function(name, params, body) => {
+                //Defined before the body so the function can call itself
+                self.declare(name);
+                self.define(name);
+                self.resolve_function(params, body, FunctionType::Function);
+            },
+            Stmt::If(condition, then_branch, else_branch) => {
+                self.resolve_expression(condition);
+                self.resolve_statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.resolve_statement(else_branch);
+                }
+            },
+            Stmt::Return(keyword, value) => {
+                if self.current_function == FunctionType::None {
+                    self.error(keyword, ErrorCode::ReturnFromTopLevel, "Can't return from top-level code.");
+                }
+                if self.current_function == FunctionType::Initializer && value.is_some() {
+                    self.error(keyword, ErrorCode::ReturnValueFromInitializer, "Can't return a value from an initializer.");
+                }
+                if let Some(value) = value {
+                    self.resolve_expression(value);
+                }
+            },
+            Stmt::Var(name, initializer) => {
+                self.declare(name);
+                self.resolve_expression(initializer);
+                self.define(name);
+            },
+            Stmt::While(condition, body) => {
+                self.resolve_expression(condition);
+                self.resolve_statement(body);
+            }
+        }
+    }
+
+    fn resolve_class(&mut self, name: &Token, superclass: &mut Option<Expr>, methods: &mut [Stmt]) {
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::Class;
+        self.declare(name);
+        self.define(name);
+
+        if let Some(Expr::Variable(superclass_name, _)) = superclass {
+            if superclass_name.lexeme == name.lexeme {
+                self.error(superclass_name, ErrorCode::InheritFromSelf, "A class can't inherit from itself.");
+            }
+        }
+        if let Some(superclass) = superclass {
+            self.current_class = ClassType::Subclass;
+            self.resolve_expression(superclass);
+            //Methods of a subclass close over an environment holding 'super'
+            self.begin_scope();
+            self.define_keyword(TokenType::SUPER, "super");
+        }
+
+        //Methods are bound in an environment holding only 'this'
+        self.begin_scope();
+        self.define_keyword(TokenType::THIS, "this");
+        for method in methods.iter_mut() {
+            if let Stmt::Function(name, params, body) = method {
+                let function_type = if name.lexeme == "init" { FunctionType::Initializer } else { FunctionType::Method };
+                self.resolve_function(params, body, function_type);
+            }
+        }
+        self.end_scope();
+        if superclass.is_some() {
+            self.end_scope();
+        }
+        self.current_class = enclosing_class;
+    }
+
+    //Parameters and the body share one scope, the same environment the call creates
+    fn resolve_function(&mut self, params: &[Token], body: &mut [Stmt], function_type: FunctionType) {
+        let enclosing_function = self.current_function;
+        self.current_function = function_type;
+        self.begin_scope();
+        for param in params {
+            self.declare(param);
+            self.define(param);
+        }
+        self.resolve_statements(body);
+        self.end_scope();
+        self.current_function = enclosing_function;
+    }
+
+    fn resolve_expression(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Assign(name, depth, value) => {
+                self.resolve_expression(value);
+                *depth = self.resolve_local(name);
+            },
+            Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            },
+            Expr::Call(callee, _, arguments) => {
+                self.resolve_expression(callee);
+                for argument in arguments.iter_mut() {
+                    self.resolve_expression(argument);
+                }
+            },
+            Expr::Get(object, _) => self.resolve_expression(object),
+            Expr::Set(object, _, value) => {
+                self.resolve_expression(value);
+                self.resolve_expression(object);
+            },
+            Expr::Grouping(expr) | Expr::Unary(_, expr) => self.resolve_expression(expr),
+            Expr::Literal(_) => (),
+            Expr::This(keyword, depth) => {
+                if self.current_class == ClassType::None {
+                    self.error(keyword, ErrorCode::ThisOutsideClass, "Can't use 'this' outside of a class.");
+                    return;
+                }
+                *depth = self.resolve_local(keyword);
+            },
+            Expr::Super(keyword, _, depth) => {
+                match self.current_class {
+                    ClassType::None => self.error(keyword, ErrorCode::InvalidSuper, "Can't use 'super' outside of a class."),
+                    ClassType::Class => self.error(keyword, ErrorCode::InvalidSuper, "Can't use 'super' in a class with no superclass."),
+                    ClassType::Subclass => *depth = self.resolve_local(keyword)
+                }
+            },
+            Expr::Variable(name, depth) => {
+                if self.scopes.last().and_then(|scope| scope.get(&name.lexeme)).is_some_and(|variable| !variable.defined) {
+                    self.error(name, ErrorCode::ReadInOwnInitializer, "Can't read local variable in its own initializer.");
+                }
+                *depth = self.resolve_local(name);
+            }
+        }
+    }
+
+    //Number of scopes between the innermost one and the one declaring name, None for globals
+    fn resolve_local(&self, name: &Token) -> Option<usize> {
+        return self.scopes.iter().rev().position(|scope| scope.contains_key(&name.lexeme));
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        if let Some(existing) = scope.get(&name.lexeme) {
+            let diagnostic = Diagnostic::at(name, ErrorCode::DuplicateVariable, format!("Already a variable with this name in this scope."));
+            let diagnostic = diagnostic.with_note(format!("'{}' was first declared here", name.lexeme), Some(&existing.name));
+            self.diagnostics.push(diagnostic);
+            return;
+        }
+        scope.insert(name.lexeme.clone(), Variable { name: name.clone(), defined: false });
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(variable) = self.scopes.last_mut().and_then(|scope| scope.get_mut(&name.lexeme)) {
+            variable.defined = true;
+        }
+    }
+
+    //The innermost scope holds 'this' or 'super' for the methods inside it
+    fn define_keyword(&mut self, token_type: TokenType, keyword: &str) {
+        let variable = Variable { name: Token::synthetic(token_type, keyword, 0), defined: true };
+        self.scopes.last_mut().unwrap().insert(format!("{}", keyword), variable);
+    }
+
+    fn error(&mut self, token: &Token, code: ErrorCode, message: &str) {
+        self.diagnostics.push(Diagnostic::at(token, code, message.to_string()));
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{diagnostics::Diagnostic, interpreter::Interpreter, virtual_machine::{interpret_vm, InterpretResult, InterpreterOutput}};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Backend {
+    Tree,
+    Vm
+}
+
+pub struct Options {
+    pub backend: Backend,
+    //Print the compiled chunks before running them
+    pub disassemble: bool,
+    //Print the stack and each instruction as the VM executes it
+    pub trace: bool
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        return Options { backend: Backend::Vm, disassemble: false, trace: false };
+    }
+}
+
+//Runs a whole source file on a fresh interpreter of the chosen backend
+pub fn run(source: String, options: &Options) -> InterpretResult {
+    match options.backend {
+        Backend::Vm => report(interpret_vm(source.clone(), options.disassemble, options.trace), &source),
+        Backend::Tree => run_tree_walk(&mut Interpreter::new(), source, false)
+    }
+}
+
+//Printed output has already been streamed to stdout, compile and runtime errors go to stderr.
+//Compile errors are shown against the source they came from
+pub fn report(output: InterpreterOutput, source: &str) -> InterpretResult {
+    if !output.diagnostics.is_empty() {
+        render(&output.diagnostics, source);
+        return output.result;
+    }
+    for line in output.errors {
+        eprintln!("{}", line);
+    }
+    for frame in output.stack_trace {
+        eprintln!("{}", frame);
+    }
+    return output.result;
+}
+
+pub fn render(diagnostics: &[Diagnostic], source: &str) {
+    for diagnostic in diagnostics {
+        eprint!("{}", diagnostic.render(source));
+    }
+}
+
+//repl lets top level expressions print their value like the VM's REPL mode
+pub fn run_tree_walk(interpreter: &mut Interpreter, source: String, repl: bool) -> InterpretResult {
+    let output = if repl { interpreter.interpret_repl(source.clone()) } else { interpreter.interpret_source(source.clone()) };
+    return report(output, &source);
+}
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
 use std::fmt;
+use std::ops::Range;
+
+use crate::diagnostics::ErrorCode;
 #[derive(Clone, Debug, PartialEq, Copy)]
 pub enum TokenType {
   // Single-character tokens.
@@ -69,18 +72,38 @@ pub struct Token {
   pub token_type: TokenType,
   pub lexeme: String,
   pub literal: Option<Literal>,
-  pub line: usize
+  pub line: usize,
+  //1-based, counted in characters so it matches what an editor shows
+  pub column: usize,
+  //Byte range of the lexeme in the source
+  pub span: Range<usize>
+}
+
+impl Token {
+  //Tokens the compiler makes up, such as "this" and "super", point at the start of the source
+  pub fn synthetic(token_type: TokenType, lexeme: &str, line: usize) -> Token {
+    return Token { token_type, lexeme: String::from(lexeme), literal: None, line, column: 1, span: 0..0 };
+  }
+
+  //The kind of scan error an error token holds
+  pub fn error_code(&self) -> ErrorCode {
+    if self.lexeme.starts_with('"') {
+      return ErrorCode::UnterminatedString;
+    }
+    return ErrorCode::UnexpectedCharacter;
+  }
 }
 
 impl fmt::Display for Token {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
       write!(
           f,
-          "Token {{ ty: {:?}, lexeme: \"{}\", literal: {:?}, line: {:?}}}\n",
+          "Token {{ ty: {:?}, lexeme: \"{}\", literal: {:?}, line: {:?}, column: {:?}}}\n",
           self.token_type,
           self.lexeme,
           self.literal,
           self.line,
+          self.column,
       )
   }
 }
@@ -92,6 +115,10 @@ pub struct Scanner {
   pub start: usize,
   pub current: usize,
   pub line: usize,
+  //Byte offset where the current line begins, used to work out columns
+  pub line_start: usize,
+  //Column of the token being scanned, taken before a multi-line string moves line_start
+  pub start_column: usize,
   pub keywords: HashMap<String, TokenType>
 }
 
@@ -115,6 +142,8 @@ impl Default for Scanner {
         current: 0,
         start: 0,
         line: 1,
+        line_start: 0,
+        start_column: 1,
         keywords: HashMap::from([
             (String::from("and"), TokenType::AND),
             (String::from("class"), TokenType::CLASS),
@@ -153,7 +182,8 @@ impl Scanner {
     }
   }
 
-  fn scan_tokens(&mut self, input: String) {
+  //Scans the whole input up front, the tree-walk parser works on the full token list
+  pub fn scan_tokens(&mut self, input: String) {
     self.source = input;
     while true {
         let token = self.scan_token();
@@ -164,12 +194,14 @@ impl Scanner {
     }
   }
   pub fn scan_token (&mut self) -> Token {
+    self.skip_whitespace();
     self.start = self.current;
+    self.start_column = self.source[self.line_start..self.start].chars().count() + 1;
     if self.scan_finished() {
       return self.add_token(TokenType::EOF)
     }
-    let _char = self.advance();
-    match _char {
+    let char = self.advance();
+    match char {
       '(' => self.add_token(TokenType::LEFT_PAREN),
       ')' => self.add_token(TokenType::RIGHT_PAREN),
       '{' => self.add_token(TokenType::LEFT_BRACE),
@@ -209,27 +241,13 @@ impl Scanner {
         } else {
           TokenType::LESS
         })}, 
-      '/' => {
-        let matches_eq = self._match('/');
-        if matches_eq {
-          //Read comment
-          while self.peek() != '\n' && !self.scan_finished() {
-            self.advance();
-          }
-          return self.scan_token()
-        } else {
-          self.add_token(TokenType::SLASH)
-        }},
-      ' ' => self.scan_token(),
-      '\r' => self.scan_token(),
-      '\t' => self.scan_token(),
-      '\n' => {self.line += 1; self.scan_token()},
+      '/' => self.add_token(TokenType::SLASH),
       '"' => self.string(),
       
       _ => {
-        if _char.is_digit(10) {
+        if char.is_digit(10) {
           self.number()
-        } else if _char.is_alphabetic() {
+        } else if char.is_alphabetic() || char == '_' {
           self.identifier()
         } else {
           return self.add_token_literal(TokenType::TOKEN_ERROR,Some(Literal::Str(format!("Unexpected character"))));
@@ -239,8 +257,26 @@ impl Scanner {
     }
   }
 
+  //Skips whitespace and comments, advance keeps track of the lines
+  fn skip_whitespace(&mut self) {
+    loop {
+      match self.peek() {
+        ' ' | '\r' | '\t' | '\n' => {
+          self.advance();
+        },
+        '/' if self.peek_next() == '/' => {
+          //Read comment
+          while self.peek() != '\n' && !self.scan_finished() {
+            self.advance();
+          }
+        },
+        _ => return
+      }
+    }
+  }
+
   fn identifier(&mut self) -> Token {
-    while self.peek().is_alphanumeric() {
+    while self.peek().is_alphanumeric() || self.peek() == '_' {
       self.advance();
     }
     let _text = &self.source[self.start..self.current];
@@ -271,9 +307,6 @@ impl Scanner {
   }
   fn string(&mut self) -> Token {
     while self.peek() != '"'  && !self.scan_finished() {
-      if self.peek() == '\n' {
-        self.line += 1;
-      }
       self.advance();
     }
 
@@ -293,10 +326,10 @@ impl Scanner {
     if self.scan_finished() {
       return false
     }
-    if self.source.chars().nth(self.current) != Some(expected) {
+    if self.peek() != expected {
       return false;
     }
-    self.current += 1;
+    self.current += expected.len_utf8();
     return true;
   }
 
@@ -313,29 +346,33 @@ impl Scanner {
       token_type: token_type,
       lexeme: String::from(text),
       literal: literal,
-      line: self.line 
+      line: self.line,
+      column: self.start_column,
+      span: self.start..self.current
     };
     self.tokens.push(token.clone());
     return token;
   }
 
+  //current is a byte offset, so multi-byte characters move it by more than one
   fn advance(&mut self) -> char {
-    self.current += 1;
-    return self.source.chars().nth(self.current-1).unwrap();
+    let char = self.peek();
+    self.current += char.len_utf8();
+    if char == '\n' {
+      self.line += 1;
+      self.line_start = self.current;
+    }
+    return char;
   }
 
   fn peek(&self) -> char {
-    if self.scan_finished() {
-      return '\0'
-    }
-    return self.source.chars().nth(self.current).unwrap();
+    return self.source[self.current..].chars().next().unwrap_or('\0');
   }
 
   fn peek_next(&self) -> char {
-    if self.current + 1 >= self.source.len() {
-      return '\0'
-    }
-    return self.source.chars().nth(self.current+1).unwrap();
+    let mut chars = self.source[self.current..].chars();
+    chars.next();
+    return chars.next().unwrap_or('\0');
   }
 
 
@@ -0,0 +1,399 @@
+use std::rc::Rc;
+
+use crate::{bytecode::{Chunk, OpCode, Value}, compiler::{compile, CompilerOutput, Function}, diagnostics::Diagnostic, heap::{Heap, ObjRef, Object}};
+
+//A .loxc file is the magic bytes, a format version, a checksum of everything after it and then the script function.
+//Functions are written as their name, arity, upvalue count, slot count, code, line table and constants, nested functions inline.
+//All integers are little endian
+const MAGIC: &[u8; 4] = b"LOXC";
+//Bump whenever the layout or the instruction set changes
+pub const FORMAT_VERSION: u16 = 2;
+const HEADER_LENGTH: usize = 10;
+//Deeper than any function the compiler can nest, stops a crafted file from overflowing the stack while loading
+const MAX_FUNCTION_DEPTH: usize = 256;
+
+const TAG_NIL: u8 = 0;
+const TAG_BOOL: u8 = 1;
+const TAG_NUMBER: u8 = 2;
+const TAG_STRING: u8 = 3;
+const TAG_FUNCTION: u8 = 4;
+
+pub enum LoadError {
+    NotBytecode,
+    UnsupportedVersion(u16),
+    ChecksumMismatch,
+    Truncated,
+    Corrupted(String)
+}
+
+impl LoadError {
+    pub fn message(&self) -> String {
+        match self {
+            LoadError::NotBytecode => format!("Not a Lox bytecode file."),
+            LoadError::UnsupportedVersion(version) => format!("Unsupported bytecode version {}, expected {}.", version, FORMAT_VERSION),
+            LoadError::ChecksumMismatch => format!("Checksum mismatch, the file is corrupted."),
+            LoadError::Truncated => format!("Unexpected end of file, the file is truncated."),
+            LoadError::Corrupted(reason) => format!("Corrupted bytecode: {}", reason)
+        }
+    }
+}
+
+//Compiles source straight to the bytes of a .loxc file
+pub fn compile_to_bytecode(src: String) -> Result<Vec<u8>, Vec<Diagnostic>> {
+    let mut heap = Heap::default();
+    match compile(src, &mut heap) {
+        CompilerOutput::Success(function) => Ok(serialize(function, &heap)),
+        CompilerOutput::Error(error) => Err(error)
+    }
+}
+
+pub fn serialize(function: ObjRef, heap: &Heap) -> Vec<u8> {
+    let mut body = vec![];
+    write_function(&mut body, heap.function(function), heap);
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend(FORMAT_VERSION.to_le_bytes());
+    bytes.extend(checksum(&body).to_le_bytes());
+    bytes.extend(body);
+    return bytes;
+}
+
+//Loads the script function into the heap, checking the file is intact before trusting any of it
+pub fn deserialize(bytes: &[u8], heap: &mut Heap) -> Result<ObjRef, LoadError> {
+    if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(LoadError::NotBytecode);
+    }
+    if bytes.len() < HEADER_LENGTH {
+        return Err(LoadError::Truncated);
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != FORMAT_VERSION {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+    let body = &bytes[HEADER_LENGTH..];
+    if u32::from_le_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]) != checksum(body) {
+        return Err(LoadError::ChecksumMismatch);
+    }
+
+    let mut reader = Reader { bytes: body, position: 0 };
+    let function = reader.read_function(heap, 0)?;
+    if reader.position != body.len() {
+        return Err(LoadError::Corrupted(format!("trailing bytes after the script.")));
+    }
+    return Ok(function);
+}
+
+fn write_function(bytes: &mut Vec<u8>, function: &Function, heap: &Heap) {
+    match &function.name {
+        Some(name) => {
+            bytes.push(1);
+            write_string(bytes, name);
+        },
+        None => bytes.push(0)
+    }
+    bytes.push(function.arity as u8);
+    bytes.extend((function.upvalue_count as u16).to_le_bytes());
+    bytes.extend((function.slot_count as u16).to_le_bytes());
+
+    let chunk = &function.chunk;
+    bytes.extend((chunk.code.len() as u32).to_le_bytes());
+    bytes.extend(chunk.code.iter());
+    bytes.extend((chunk.lines.len() as u32).to_le_bytes());
+    for (line, count) in chunk.lines.iter() {
+        bytes.extend((*line as u32).to_le_bytes());
+        bytes.extend((*count as u32).to_le_bytes());
+    }
+
+    bytes.extend((chunk.constant.len() as u32).to_le_bytes());
+    for constant in chunk.constant.iter() {
+        match constant {
+            Value::Nil => bytes.push(TAG_NIL),
+            Value::Bool(b) => {
+                bytes.push(TAG_BOOL);
+                bytes.push(*b as u8);
+            },
+            Value::Number(n) => {
+                bytes.push(TAG_NUMBER);
+                bytes.extend(n.to_le_bytes());
+            },
+            Value::String(string) => {
+                bytes.push(TAG_STRING);
+                write_string(bytes, heap.string(*string));
+            },
+            Value::Function(nested) => {
+                bytes.push(TAG_FUNCTION);
+                write_function(bytes, heap.function(*nested), heap);
+            },
+            //The compiler only ever creates the constants above
+            Value::Closure(_) | Value::Class(_) | Value::Instance(_) | Value::BoundMethod(_) | Value::Native(_) => panic!("Runtime object in constant table")
+        }
+    }
+}
+
+fn write_string(bytes: &mut Vec<u8>, string: &str) {
+    bytes.extend((string.len() as u32).to_le_bytes());
+    bytes.extend(string.as_bytes());
+}
+
+//FNV-1a, enough to catch accidental corruption
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    for byte in bytes {
+        hash ^= *byte as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    return hash;
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl Reader<'_> {
+    fn read_bytes(&mut self, length: usize) -> Result<&[u8], LoadError> {
+        if self.bytes.len() - self.position < length {
+            return Err(LoadError::Truncated);
+        }
+        let bytes = &self.bytes[self.position..self.position + length];
+        self.position += length;
+        return Ok(bytes);
+    }
+
+    fn read_u8(&mut self) -> Result<u8, LoadError> {
+        return Ok(self.read_bytes(1)?[0]);
+    }
+
+    fn read_u16(&mut self) -> Result<u16, LoadError> {
+        let bytes = self.read_bytes(2)?;
+        return Ok(u16::from_le_bytes([bytes[0], bytes[1]]));
+    }
+
+    fn read_u32(&mut self) -> Result<usize, LoadError> {
+        let bytes = self.read_bytes(4)?;
+        return Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize);
+    }
+
+    fn read_string(&mut self) -> Result<String, LoadError> {
+        let length = self.read_u32()?;
+        let bytes = self.read_bytes(length)?.to_vec();
+        return String::from_utf8(bytes).map_err(|_| LoadError::Corrupted(format!("string is not valid UTF-8.")));
+    }
+
+    fn read_function(&mut self, heap: &mut Heap, depth: usize) -> Result<ObjRef, LoadError> {
+        if depth > MAX_FUNCTION_DEPTH {
+            return Err(LoadError::Corrupted(format!("functions are nested too deeply.")));
+        }
+        let name = match self.read_u8()? {
+            0 => None,
+            1 => Some(self.read_string()?),
+            flag => return Err(LoadError::Corrupted(format!("invalid function name flag {}.", flag)))
+        };
+        let arity = self.read_u8()? as usize;
+        let upvalue_count = self.read_u16()? as usize;
+        let slot_count = self.read_u16()? as usize;
+        //The callee and the parameters always have a slot, and slots are addressed with a single byte
+        if slot_count <= arity || slot_count > 256 {
+            return Err(LoadError::Corrupted(format!("invalid slot count {}.", slot_count)));
+        }
+
+        let mut chunk = Chunk::default();
+        let code_length = self.read_u32()?;
+        chunk.code = self.read_bytes(code_length)?.to_vec();
+        let line_count = self.read_u32()?;
+        for _ in 0..line_count {
+            let line = self.read_u32()?;
+            let count = self.read_u32()?;
+            chunk.lines.push((line, count));
+        }
+        if chunk.lines.iter().map(|(_, count)| count).sum::<usize>() != chunk.code.len() {
+            return Err(LoadError::Corrupted(format!("line table doesn't match the code.")));
+        }
+
+        let constant_count = self.read_u32()?;
+        for _ in 0..constant_count {
+            let constant = match self.read_u8()? {
+                TAG_NIL => Value::Nil,
+                TAG_BOOL => Value::Bool(self.read_u8()? != 0),
+                TAG_NUMBER => {
+                    let bytes = self.read_bytes(8)?;
+                    Value::Number(f64::from_le_bytes(bytes.try_into().unwrap()))
+                },
+                TAG_STRING => {
+                    let string = self.read_string()?;
+                    Value::String(heap.intern(string))
+                },
+                TAG_FUNCTION => Value::Function(self.read_function(heap, depth + 1)?),
+                tag => return Err(LoadError::Corrupted(format!("unknown constant tag {}.", tag)))
+            };
+            chunk.constant.push(constant);
+        }
+
+        let function = Function { arity, chunk: Rc::new(chunk), name, upvalue_count, slot_count };
+        verify_code(&function, heap)?;
+        return Ok(heap.alloc(Object::Function(function)));
+    }
+}
+
+//Walks the instructions so the VM never reads an unknown opcode, past the end of the code, a missing constant,
+//a slot or upvalue the function doesn't have, or lands in the middle of an instruction
+fn verify_code(function: &Function, heap: &Heap) -> Result<(), LoadError> {
+    let chunk = &function.chunk;
+    //The opcode and operand width of the instruction starting at each offset
+    let mut instructions = vec![None; chunk.code.len()];
+    let mut jumps = vec![];
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        let op_code = match OpCode::from_byte(chunk.code[offset]) {
+            Some(op_code) => op_code,
+            None => return Err(LoadError::Corrupted(format!("unknown opcode {} at offset {}.", chunk.code[offset], offset)))
+        };
+        let operands = &chunk.code[offset + 1..];
+        let constant = |index: usize| match chunk.constant.get(index) {
+            Some(constant) => Ok(*constant),
+            None => Err(LoadError::Corrupted(format!("missing constant {} at offset {}.", index, offset)))
+        };
+        let name = |index: usize| match constant(index)? {
+            Value::String(_) => Ok(()),
+            _ => Err(LoadError::Corrupted(format!("expected a name constant at offset {}.", offset)))
+        };
+        let local = |slot: usize| match slot < function.slot_count {
+            true => Ok(()),
+            false => Err(LoadError::Corrupted(format!("local slot {} out of range at offset {}.", slot, offset)))
+        };
+        let upvalue = |index: usize| match index < function.upvalue_count {
+            true => Ok(()),
+            false => Err(LoadError::Corrupted(format!("upvalue {} out of range at offset {}.", index, offset)))
+        };
+        let short = |operands: &[u8]| (operands[0] as usize) << 8 | operands[1] as usize;
+
+        let operand_width = match op_code {
+            OpCode::Constant | OpCode::GetLocal | OpCode::SetLocal | OpCode::Call | OpCode::GetUpvalue | OpCode::SetUpvalue => 1,
+            OpCode::ConstantLong => 3,
+            OpCode::JumpIfFalse | OpCode::Jump | OpCode::Loop | OpCode::SwitchJump => 2,
+            OpCode::GetGlobal | OpCode::SetGlobal | OpCode::Class | OpCode::Method | OpCode::GetProperty | OpCode::SetProperty | OpCode::GetSuper => 2,
+            OpCode::DefineGlobal | OpCode::Invoke | OpCode::SuperInvoke => 3,
+            OpCode::Closure => {
+                if operands.len() < 2 {
+                    return Err(LoadError::Truncated);
+                }
+                match constant(short(operands))? {
+                    Value::Function(function) => 2 + heap.function(function).upvalue_count * 2,
+                    _ => return Err(LoadError::Corrupted(format!("expected a function constant at offset {}.", offset)))
+                }
+            },
+            _ => 0
+        };
+        if operands.len() < operand_width {
+            return Err(LoadError::Corrupted(format!("instruction at offset {} runs past the end of the code.", offset)));
+        }
+
+        match op_code {
+            OpCode::Constant => { constant(operands[0] as usize)?; },
+            OpCode::ConstantLong => { constant((operands[0] as usize) << 16 | short(&operands[1..]))?; },
+            OpCode::GetGlobal | OpCode::SetGlobal | OpCode::DefineGlobal | OpCode::Class | OpCode::Method | OpCode::GetProperty
+                | OpCode::SetProperty | OpCode::GetSuper | OpCode::Invoke | OpCode::SuperInvoke => name(short(operands))?,
+            OpCode::GetLocal | OpCode::SetLocal => local(operands[0] as usize)?,
+            OpCode::GetUpvalue | OpCode::SetUpvalue => upvalue(operands[0] as usize)?,
+            //Each captured variable is a local of this function or one of its own upvalues
+            OpCode::Closure => {
+                for capture in operands[2..operand_width].chunks(2) {
+                    match capture[0] {
+                        1 => local(capture[1] as usize)?,
+                        0 => upvalue(capture[1] as usize)?,
+                        flag => return Err(LoadError::Corrupted(format!("invalid capture flag {} at offset {}.", flag, offset)))
+                    }
+                }
+            },
+            OpCode::Jump | OpCode::JumpIfFalse | OpCode::SwitchJump => jumps.push((offset, offset + 3 + short(operands))),
+            OpCode::Loop => match (offset + 3).checked_sub(short(operands)) {
+                Some(target) => jumps.push((offset, target)),
+                None => return Err(LoadError::Corrupted(format!("loop at offset {} leaves the code.", offset)))
+            },
+            _ => ()
+        }
+        instructions[offset] = Some((op_code, operand_width));
+        offset += 1 + operand_width;
+    }
+
+    //Jumping to the end of the code would run off it just like falling through
+    for (offset, target) in jumps {
+        if instructions.get(target).is_none_or(|instruction| instruction.is_none()) {
+            return Err(LoadError::Corrupted(format!("jump at offset {} doesn't land on an instruction.", offset)));
+        }
+    }
+    return verify_stack(function, &instructions);
+}
+
+//Follows every path through the code counting the values the frame has on the stack, so no instruction pops more
+//than is there or reads a local that was never pushed. Paths meeting at an instruction must agree on the count
+fn verify_stack(function: &Function, instructions: &[Option<(OpCode, usize)>]) -> Result<(), LoadError> {
+    let code = &function.chunk.code;
+    let mut depths = vec![None; code.len()];
+    //The callee and the arguments are already on the stack when a call starts
+    let mut pending = vec![(0, function.arity + 1)];
+    while let Some((offset, depth)) = pending.pop() {
+        match depths[offset] {
+            Some(known) if known == depth => continue,
+            Some(_) => return Err(LoadError::Corrupted(format!("stack depth differs between paths reaching offset {}.", offset))),
+            None => depths[offset] = Some(depth)
+        }
+        let (op_code, operand_width) = instructions[offset].unwrap();
+        let operands = &code[offset + 1..offset + 1 + operand_width];
+        let jump = || (operands[0] as usize) << 8 | operands[1] as usize;
+
+        let (popped, pushed) = stack_effect(op_code, operands);
+        if depth < popped {
+            return Err(LoadError::Corrupted(format!("stack underflow at offset {}.", offset)));
+        }
+        let local = |slot: u8, stack_length: usize| match (slot as usize) < stack_length {
+            true => Ok(()),
+            false => Err(LoadError::Corrupted(format!("local slot {} isn't on the stack at offset {}.", slot, offset)))
+        };
+        match op_code {
+            OpCode::GetLocal | OpCode::SetLocal => local(operands[0], depth)?,
+            //A local function can capture itself, in the slot the new closure is about to be pushed to
+            OpCode::Closure => {
+                for capture in operands[2..].chunks(2).filter(|capture| capture[0] == 1) {
+                    local(capture[1], depth + 1)?;
+                }
+            },
+            _ => ()
+        }
+
+        let depth = depth - popped + pushed;
+        let next = offset + 1 + operand_width;
+        let targets = match op_code {
+            OpCode::Return => vec![],
+            OpCode::Jump => vec![next + jump()],
+            OpCode::Loop => vec![next - jump()],
+            OpCode::JumpIfFalse | OpCode::SwitchJump => vec![next, next + jump()],
+            _ => vec![next]
+        };
+        for target in targets {
+            if target == code.len() {
+                return Err(LoadError::Corrupted(format!("code doesn't end with a return.")));
+            }
+            pending.push((target, depth));
+        }
+    }
+    return Ok(());
+}
+
+//How many values an instruction pops and then pushes
+fn stack_effect(op_code: OpCode, operands: &[u8]) -> (usize, usize) {
+    match op_code {
+        OpCode::Constant | OpCode::ConstantLong | OpCode::GetGlobal | OpCode::GetLocal | OpCode::GetUpvalue | OpCode::Closure | OpCode::Class => (0, 1),
+        OpCode::Negate | OpCode::Not | OpCode::GetProperty | OpCode::SetGlobal | OpCode::SetLocal | OpCode::SetUpvalue | OpCode::JumpIfFalse => (1, 1),
+        OpCode::Add | OpCode::Subtract | OpCode::Multiply | OpCode::Divide | OpCode::Equal | OpCode::Greater | OpCode::Less => (2, 1),
+        //The class or superclass stays below for the instructions that follow
+        OpCode::Method | OpCode::Inherit | OpCode::SetProperty | OpCode::GetSuper | OpCode::SwitchJump => (2, 1),
+        OpCode::Return | OpCode::Print | OpCode::Pop | OpCode::DefineGlobal | OpCode::CloseUpvalue => (1, 0),
+        OpCode::Jump | OpCode::Loop => (0, 0),
+        //The callee and its arguments are replaced by the result
+        OpCode::Call => (operands[0] as usize + 1, 1),
+        OpCode::Invoke => (operands[2] as usize + 1, 1),
+        //The superclass is popped as well
+        OpCode::SuperInvoke => (operands[2] as usize + 2, 1)
+    }
+}
@@ -39,7 +39,7 @@ mod tests {
     }
 
     #[test]
-    fn assingment_undefined() {
-        assert_eq!(run_from_file("src/tests/assignment/undefined.lox"), ["[Line 1] Runtime Var Error", "Error message: Undefined variable 'unknown'."]);
+    fn assignment_undefined() {
+        assert_eq!(run_from_file("src/tests/assignment/undefined.lox"), ["[Line 1] Runtime Var Error", "Error Message: Undefined variable 'unknown'."]);
     }
 }
\ No newline at end of file
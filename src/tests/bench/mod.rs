@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use crate::{bench::{bench, format_json, format_table, BenchResult}, runner::Backend, virtual_machine::VirtualMachine};
+
+	const SOURCE: &str = "fun f(n) { if (n < 2) return n; return f(n - 1) + f(n - 2); } print f(10);";
+
+	#[test]
+	fn bench_counts_vm_instructions() {
+		let result = bench("f", SOURCE, Backend::Vm, 3).ok().unwrap();
+		assert_eq!(result.name, "f");
+		assert!(result.min <= result.median && result.median <= result.max);
+
+		//Every run starts on a fresh machine so the count is for one run
+		let mut vm = VirtualMachine::new();
+		vm.set_output(Box::new(|_: &str| ()));
+		vm.interpret_source(SOURCE.to_string(), false, false);
+		assert_eq!(result.instructions, Some(vm.instruction_count()));
+	}
+
+	#[test]
+	fn bench_tree_has_no_instructions() {
+		assert_eq!(bench("f", SOURCE, Backend::Tree, 1).ok().unwrap().instructions, None);
+	}
+
+	#[test]
+	fn bench_failed_run() {
+		let errors = bench("error", "print -nil;", Backend::Vm, 3).err().unwrap();
+		assert_eq!(errors, ["[Line 1] Runtime Type Error", "Error Message: Operand must be a number."]);
+		assert!(bench("error", "print -nil;", Backend::Tree, 3).is_err());
+	}
+
+	#[test]
+	fn bench_formats() {
+		let result = |name: &str, instructions| BenchResult { name: name.to_string(), min: Duration::from_millis(1), median: Duration::from_millis(2), max: Duration::from_millis(4), instructions };
+		let results = [result("fib", Some(100)), result("zoo", None)];
+		assert_eq!(format_table(&results).lines().collect::<Vec<_>>(), [
+			"benchmark            min (ms)  median (ms)     max (ms)   instructions",
+			"fib                      1.00         2.00         4.00            100",
+			"zoo                      1.00         2.00         4.00              -"
+		]);
+		assert_eq!(format_json(&results[..1], Backend::Vm, 5), "{\n  \"backend\": \"vm\",\n  \"iterations\": 5,\n  \"benchmarks\": [\n    {\"name\": \"fib\", \"min_ms\": 1.000, \"median_ms\": 2.000, \"max_ms\": 4.000, \"instructions\": 100}\n  ]\n}\n");
+		assert!(format_json(&results[1..], Backend::Tree, 1).contains("\"instructions\": null"));
+	}
+}
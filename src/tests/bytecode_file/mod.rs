@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod tests {
+	use std::{fs, path::Path, rc::Rc};
+
+	use crate::{bytecode::{Chunk, OpCode, Value}, compiler::Function, heap::{Heap, Object}, serialize::{compile_to_bytecode, deserialize, serialize}, tests::{lox_files, run_from_file, run_on_vm}};
+
+	fn compile_file(file: &str) -> Vec<u8> {
+		let src = fs::read_to_string(file).expect("Error: file doesnt exist");
+		return compile_to_bytecode(src).expect("Error: file doesnt compile");
+	}
+
+	//A well formed file holding code the compiler would never emit
+	fn crafted_file(code: &[u8], constants: Vec<Value>, heap: &mut Heap) -> Vec<u8> {
+		let mut chunk = Chunk::default();
+		for byte in code {
+			chunk.chunk_write(*byte, 1);
+		}
+		chunk.constant = constants;
+		let function = heap.alloc(Object::Function(Function { arity: 0, chunk: Rc::new(chunk), name: None, upvalue_count: 0, slot_count: 1 }));
+		return serialize(function, heap);
+	}
+
+	//Constant 0 is nil
+	fn run_crafted(code: &[u8]) -> Vec<String> {
+		return run_from_bytecode(&crafted_file(code, vec![Value::Nil], &mut Heap::default()));
+	}
+
+	fn run_from_bytecode(bytes: &[u8]) -> Vec<String> {
+		return run_on_vm(|vm| vm.interpret_bytecode(bytes, false, false));
+	}
+
+	#[test]
+	fn bytecode_file_round_trip() {
+		let files = [
+			"src/tests/closure/counter.lox",
+			"src/tests/inheritance/inherit_methods.lox",
+			"src/tests/for_loop/break_closure.lox",
+			"src/tests/limit/constant_long.lox",
+			"src/tests/string/interning.lox",
+			"src/tests/function/extra_arguments.lox",
+		];
+		for file in files {
+			assert_eq!(run_from_bytecode(&compile_file(file)), run_from_file(file), "{}", file);
+		}
+	}
+
+	#[test]
+	fn bytecode_file_compile_error() {
+		let diagnostics = compile_to_bytecode(format!("print ;")).unwrap_err();
+		assert_eq!(diagnostics[0].summary(), ["[Line 1] Error at ';'", "Error Message: Expect expression."]);
+	}
+
+	#[test]
+	fn bytecode_file_not_bytecode() {
+		assert_eq!(run_from_bytecode(b"print 1;"), ["Error loading bytecode: Not a Lox bytecode file."]);
+	}
+
+	#[test]
+	fn bytecode_file_unsupported_version() {
+		let mut bytes = compile_file("src/tests/closure/counter.lox");
+		bytes[4] = 0xff;
+		assert_eq!(run_from_bytecode(&bytes), ["Error loading bytecode: Unsupported bytecode version 255, expected 2."]);
+	}
+
+	#[test]
+	fn bytecode_file_corrupted() {
+		let mut bytes = compile_file("src/tests/closure/counter.lox");
+		let last = bytes.len() - 1;
+		bytes[last] ^= 0xff;
+		assert_eq!(run_from_bytecode(&bytes), ["Error loading bytecode: Checksum mismatch, the file is corrupted."]);
+	}
+
+	#[test]
+	fn bytecode_file_truncated_header() {
+		let bytes = compile_file("src/tests/closure/counter.lox");
+		assert_eq!(run_from_bytecode(&bytes[..6]), ["Error loading bytecode: Unexpected end of file, the file is truncated."]);
+	}
+
+	#[test]
+	fn bytecode_file_loads_every_program() {
+		let root = Path::new("src/tests");
+		let mut files = vec![];
+		lox_files(root, root, &mut files);
+		for file in files {
+			let src = fs::read_to_string(root.join(&file)).expect("Error: file doesnt exist");
+			if let Ok(bytes) = compile_to_bytecode(src) {
+				if let Err(error) = deserialize(&bytes, &mut Heap::default()) {
+					panic!("{}: {}", file, error.message());
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn bytecode_file_local_slot_out_of_range() {
+		let code = [OpCode::GetLocal as u8, 5, OpCode::Return as u8];
+		assert_eq!(run_crafted(&code), ["Error loading bytecode: Corrupted bytecode: local slot 5 out of range at offset 0."]);
+	}
+
+	#[test]
+	fn bytecode_file_upvalue_out_of_range() {
+		let code = [OpCode::Constant as u8, 0, OpCode::SetUpvalue as u8, 0, OpCode::Return as u8];
+		assert_eq!(run_crafted(&code), ["Error loading bytecode: Corrupted bytecode: upvalue 0 out of range at offset 2."]);
+	}
+
+	#[test]
+	fn bytecode_file_closure_captures_missing_local() {
+		let mut heap = Heap::default();
+		let mut chunk = Chunk::default();
+		for byte in [OpCode::GetUpvalue as u8, 0, OpCode::Return as u8] {
+			chunk.chunk_write(byte, 1);
+		}
+		let nested = heap.alloc(Object::Function(Function { arity: 0, chunk: Rc::new(chunk), name: None, upvalue_count: 1, slot_count: 1 }));
+		let code = [OpCode::Closure as u8, 0, 0, 1, 3, OpCode::Return as u8];
+		let bytes = crafted_file(&code, vec![Value::Function(nested)], &mut heap);
+		assert_eq!(run_from_bytecode(&bytes), ["Error loading bytecode: Corrupted bytecode: local slot 3 out of range at offset 0."]);
+	}
+
+	#[test]
+	fn bytecode_file_jump_into_instruction() {
+		let code = [OpCode::Jump as u8, 0, 1, OpCode::Constant as u8, 0, OpCode::Return as u8];
+		assert_eq!(run_crafted(&code), ["Error loading bytecode: Corrupted bytecode: jump at offset 0 doesn't land on an instruction."]);
+	}
+
+	#[test]
+	fn bytecode_file_stack_underflow() {
+		//Only the script itself is on the stack to begin with
+		let code = [OpCode::Pop as u8, OpCode::Pop as u8, OpCode::Return as u8];
+		assert_eq!(run_crafted(&code), ["Error loading bytecode: Corrupted bytecode: stack underflow at offset 1."]);
+	}
+
+	#[test]
+	fn bytecode_file_paths_disagree_on_stack_depth() {
+		let code = [OpCode::Constant as u8, 0, OpCode::JumpIfFalse as u8, 0, 2, OpCode::Constant as u8, 0, OpCode::Return as u8];
+		assert_eq!(run_crafted(&code), ["Error loading bytecode: Corrupted bytecode: stack depth differs between paths reaching offset 7."]);
+	}
+
+	#[test]
+	fn bytecode_file_falls_off_the_end() {
+		let code = [OpCode::Constant as u8, 0];
+		assert_eq!(run_crafted(&code), ["Error loading bytecode: Corrupted bytecode: code doesn't end with a return."]);
+	}
+
+	#[test]
+	fn bytecode_file_nested_too_deeply() {
+		let mut heap = Heap::default();
+		let mut function = heap.alloc(Object::Function(Function { arity: 0, chunk: Rc::new(Chunk::default()), name: None, upvalue_count: 0, slot_count: 1 }));
+		for _ in 0..300 {
+			let mut chunk = Chunk::default();
+			chunk.constant.push(Value::Function(function));
+			function = heap.alloc(Object::Function(Function { arity: 0, chunk: Rc::new(chunk), name: None, upvalue_count: 0, slot_count: 1 }));
+		}
+		assert_eq!(run_from_bytecode(&serialize(function, &heap)), ["Error loading bytecode: Corrupted bytecode: functions are nested too deeply."]);
+	}
+}
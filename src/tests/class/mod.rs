@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::{run_from_file, run_from_file_on_tree};
+
+	#[test]
+	fn class_empty() {
+		assert_eq!(run_from_file("src/tests/class/empty.lox"), ["Foo"]);
+	}
+
+	#[test]
+	fn class_identity() {
+		assert_eq!(run_from_file("src/tests/class/identity.lox"), ["true", "false"]);
+		assert_eq!(run_from_file_on_tree("src/tests/class/identity.lox"), ["true", "false"]);
+	}
+
+	#[test]
+	fn class_inherit_self() {
+		assert_eq!(run_from_file("src/tests/class/inherit_self.lox"), ["[Line 1] Error at 'Foo'", "Error Message: A class can't inherit from itself."]);
+	}
+
+	#[test]
+	fn class_inherited_method() {
+		assert_eq!(run_from_file("src/tests/class/inherited_method.lox"), ["in foo", "in bar", "in baz"]);
+	}
+
+	#[test]
+	fn class_local_inherit_other() {
+		assert_eq!(run_from_file("src/tests/class/local_inherit_other.lox"), ["B"]);
+	}
+
+	#[test]
+	fn class_local_inherit_self() {
+		assert_eq!(run_from_file("src/tests/class/local_inherit_self.lox"), ["[Line 2] Error at 'Foo'", "Error Message: A class can't inherit from itself.", "[Line 5] Error at 'EOF'", "Error Message: Expect '}' after block."]);
+	}
+
+	#[test]
+	fn class_local_reference_self() {
+		assert_eq!(run_from_file("src/tests/class/local_reference_self.lox"), ["Foo"]);
+	}
+
+	#[test]
+	fn class_reference_self() {
+		assert_eq!(run_from_file("src/tests/class/reference_self.lox"), ["Foo"]);
+	}
+
+}
\ No newline at end of file
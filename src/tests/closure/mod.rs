@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::{run_from_file, run_from_file_on_tree};
+
+	#[test]
+	fn closure_assign_to_closure() {
+		assert_eq!(run_from_file("src/tests/closure/assign_to_closure.lox"), ["local", "after f", "after f", "after g"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/assign_to_closure.lox"), ["local", "after f", "after f", "after g"]);
+	}
+
+	#[test]
+	fn closure_assign_to_shadowed_later() {
+		assert_eq!(run_from_file("src/tests/closure/assign_to_shadowed_later.lox"), ["inner", "assigned"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/assign_to_shadowed_later.lox"), ["inner", "assigned"]);
+	}
+
+	#[test]
+	fn closure_close_over_function_parameter() {
+		assert_eq!(run_from_file("src/tests/closure/close_over_function_parameter.lox"), ["param"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/close_over_function_parameter.lox"), ["param"]);
+	}
+
+	#[test]
+	fn closure_close_over_later_variable() {
+		assert_eq!(run_from_file("src/tests/closure/close_over_later_variable.lox"), ["b", "a"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/close_over_later_variable.lox"), ["b", "a"]);
+	}
+
+	#[test]
+	fn closure_close_over_method_parameter() {
+		assert_eq!(run_from_file("src/tests/closure/close_over_method_parameter.lox"), ["param"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/close_over_method_parameter.lox"), ["param"]);
+	}
+
+	#[test]
+	fn closure_closed_closure_in_function() {
+		assert_eq!(run_from_file("src/tests/closure/closed_closure_in_function.lox"), ["local"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/closed_closure_in_function.lox"), ["local"]);
+	}
+
+	#[test]
+	fn closure_counter() {
+		assert_eq!(run_from_file("src/tests/closure/counter.lox"), ["1", "2", "1", "3"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/counter.lox"), ["1", "2", "1", "3"]);
+	}
+
+	#[test]
+	fn closure_loop_variables() {
+		assert_eq!(run_from_file("src/tests/closure/loop_variables.lox"), ["0", "1"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/loop_variables.lox"), ["0", "1"]);
+	}
+
+	#[test]
+	fn closure_nested() {
+		assert_eq!(run_from_file("src/tests/closure/nested.lox"), ["outer"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/nested.lox"), ["outer"]);
+	}
+
+	#[test]
+	fn closure_nested_closure() {
+		assert_eq!(run_from_file("src/tests/closure/nested_closure.lox"), ["a", "b", "c"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/nested_closure.lox"), ["a", "b", "c"]);
+	}
+
+	#[test]
+	fn closure_open_closure_in_function() {
+		assert_eq!(run_from_file("src/tests/closure/open_closure_in_function.lox"), ["local"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/open_closure_in_function.lox"), ["local"]);
+	}
+
+	#[test]
+	fn closure_recursion() {
+		assert_eq!(run_from_file("src/tests/closure/recursion.lox"), ["55"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/recursion.lox"), ["55"]);
+	}
+
+	#[test]
+	fn closure_reference_closure_multiple_times() {
+		assert_eq!(run_from_file("src/tests/closure/reference_closure_multiple_times.lox"), ["a", "a"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/reference_closure_multiple_times.lox"), ["a", "a"]);
+	}
+
+	#[test]
+	fn closure_reuse_closure_slot() {
+		assert_eq!(run_from_file("src/tests/closure/reuse_closure_slot.lox"), ["a"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/reuse_closure_slot.lox"), ["a"]);
+	}
+
+	#[test]
+	fn closure_shadow_closure_with_local() {
+		assert_eq!(run_from_file("src/tests/closure/shadow_closure_with_local.lox"), ["closure", "shadow", "closure"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/shadow_closure_with_local.lox"), ["closure", "shadow", "closure"]);
+	}
+
+	#[test]
+	fn closure_shared_variable() {
+		assert_eq!(run_from_file("src/tests/closure/shared_variable.lox"), ["before", "after"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/shared_variable.lox"), ["before", "after"]);
+	}
+
+	#[test]
+	fn closure_sibling_closures() {
+		assert_eq!(run_from_file("src/tests/closure/sibling_closures.lox"), ["0", "2"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/sibling_closures.lox"), ["0", "2"]);
+	}
+
+	#[test]
+	fn closure_static_scope() {
+		assert_eq!(run_from_file("src/tests/closure/static_scope.lox"), ["global", "global"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/static_scope.lox"), ["global", "global"]);
+	}
+
+	#[test]
+	fn closure_unused_closure() {
+		assert_eq!(run_from_file("src/tests/closure/unused_closure.lox"), ["ok"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/unused_closure.lox"), ["ok"]);
+	}
+
+	#[test]
+	fn closure_unused_later_closure() {
+		assert_eq!(run_from_file("src/tests/closure/unused_later_closure.lox"), ["a"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/unused_later_closure.lox"), ["a"]);
+	}
+
+	#[test]
+	fn closure_write_loop_variable() {
+		assert_eq!(run_from_file("src/tests/closure/write_loop_variable.lox"), ["3", "7", "11"]);
+		assert_eq!(run_from_file_on_tree("src/tests/closure/write_loop_variable.lox"), ["3", "7", "11"]);
+	}
+
+}
\ No newline at end of file
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::{run_from_file, run_from_file_on_tree};
+
+	#[test]
+	fn constructor_arguments() {
+		assert_eq!(run_from_file("src/tests/constructor/arguments.lox"), ["init", "1", "2"]);
+		assert_eq!(run_from_file_on_tree("src/tests/constructor/arguments.lox"), ["init", "1", "2"]);
+	}
+
+	#[test]
+	fn constructor_call_init_early_return() {
+		assert_eq!(run_from_file("src/tests/constructor/call_init_early_return.lox"), ["init", "init", "Foo instance"]);
+	}
+
+	#[test]
+	fn constructor_call_init_explicitly() {
+		assert_eq!(run_from_file("src/tests/constructor/call_init_explicitly.lox"), ["Foo.init(one)", "Foo.init(two)", "Foo instance", "init"]);
+	}
+
+	#[test]
+	fn constructor_default() {
+		assert_eq!(run_from_file("src/tests/constructor/default.lox"), ["Foo instance"]);
+	}
+
+	#[test]
+	fn constructor_default_arguments() {
+		assert_eq!(run_from_file("src/tests/constructor/default_arguments.lox"), ["[Line 3] Runtime Function Error", "Error Message: Expected 0 arguments but got 3."]);
+	}
+
+	#[test]
+	fn constructor_early_return() {
+		assert_eq!(run_from_file("src/tests/constructor/early_return.lox"), ["init", "Foo instance"]);
+	}
+
+	#[test]
+	fn constructor_extra_arguments() {
+		assert_eq!(run_from_file("src/tests/constructor/extra_arguments.lox"), ["[Line 8] Runtime Function Error", "Error Message: Expected 2 arguments but got 4."]);
+	}
+
+	#[test]
+	fn constructor_init_not_method() {
+		assert_eq!(run_from_file("src/tests/constructor/init_not_method.lox"), ["not initializer"]);
+		assert_eq!(run_from_file_on_tree("src/tests/constructor/init_not_method.lox"), ["not initializer"]);
+	}
+
+	#[test]
+	fn constructor_init_returns_this() {
+		assert_eq!(run_from_file("src/tests/constructor/init_returns_this.lox"), ["true", "2"]);
+		assert_eq!(run_from_file_on_tree("src/tests/constructor/init_returns_this.lox"), ["true", "2"]);
+	}
+
+	#[test]
+	fn constructor_init_sets_fields() {
+		assert_eq!(run_from_file("src/tests/constructor/init_sets_fields.lox"), ["1", "2"]);
+		assert_eq!(run_from_file_on_tree("src/tests/constructor/init_sets_fields.lox"), ["1", "2"]);
+	}
+
+	#[test]
+	fn constructor_missing_arguments() {
+		assert_eq!(run_from_file("src/tests/constructor/missing_arguments.lox"), ["[Line 5] Runtime Function Error", "Error Message: Expected 2 arguments but got 1."]);
+	}
+
+	#[test]
+	fn constructor_return_in_nested_function() {
+		assert_eq!(run_from_file("src/tests/constructor/return_in_nested_function.lox"), ["bar", "Foo instance"]);
+	}
+
+	#[test]
+	fn constructor_return_value() {
+		assert_eq!(run_from_file("src/tests/constructor/return_value.lox"), ["[Line 3] Error at 'return'", "Error Message: Can't return a value from an initializer."]);
+	}
+
+}
\ No newline at end of file
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+	use crate::{diagnostics::{Diagnostic, ErrorCode}, heap::Heap, compiler::{compile, CompilerOutput}, parser, resolver, scanner::{Scanner, TokenType}};
+
+	fn compile_errors(src: &str) -> Vec<Diagnostic> {
+		match compile(src.to_string(), &mut Heap::default()) {
+			CompilerOutput::Success(_) => panic!("Error: {} compiled", src),
+			CompilerOutput::Error(diagnostics) => diagnostics
+		}
+	}
+
+	fn parse_errors(src: &str) -> Vec<Diagnostic> {
+		let mut scanner = Scanner::default();
+		scanner.scan_tokens(src.to_string());
+		return parser::parse(scanner.tokens).err().expect("Error: source parsed");
+	}
+
+	fn resolve_errors(src: &str) -> Vec<Diagnostic> {
+		let mut scanner = Scanner::default();
+		scanner.scan_tokens(src.to_string());
+		let mut statements = parser::parse(scanner.tokens).expect("Error: source didn't parse");
+		return resolver::resolve(&mut statements).err().expect("Error: source resolved");
+	}
+
+	#[test]
+	fn diagnostics_token_columns_and_spans() {
+		let mut scanner = Scanner::default();
+		scanner.scan_tokens(format!("var a = 1;\n  print \"héllo\" + a;"));
+		let print = &scanner.tokens[5];
+		assert_eq!((print.token_type, print.line, print.column, print.span.clone()), (TokenType::PRINT, 2, 3, 13..18));
+		//Columns count characters, spans count bytes
+		let plus = &scanner.tokens[7];
+		assert_eq!((plus.column, plus.span.clone()), (17, 28..29));
+		assert_eq!(&scanner.source[scanner.tokens[6].span.clone()], "\"héllo\"");
+	}
+
+	#[test]
+	fn diagnostics_render_caret() {
+		let src = "var a = 1;\nprint a";
+		let diagnostics = compile_errors(src);
+		assert_eq!(diagnostics[0].code, ErrorCode::ExpectToken);
+		assert_eq!(diagnostics[0].render(src), "error[E0004]: Expect ';' after value.\n --> line 2, column 8\n  |\n2 | print a\n  |        ^\n");
+	}
+
+	#[test]
+	fn diagnostics_render_underlines_token() {
+		let src = "print 1;\n\tprint this;";
+		let diagnostics = compile_errors(src);
+		assert_eq!(diagnostics[0].render(src), "error[E0011]: Can't use 'this' outside of a class.\n --> line 2, column 8\n  |\n2 | \tprint this;\n  | \t      ^^^^\n");
+	}
+
+	#[test]
+	fn diagnostics_duplicate_variable_note() {
+		let src = "{\n  var a = 1;\n  var a = 2;\n}";
+		let diagnostics = compile_errors(src);
+		assert_eq!(diagnostics[0].code, ErrorCode::DuplicateVariable);
+		assert_eq!(diagnostics[0].summary(), ["[Line 3] Error at 'a'", "Error Message: Already a variable with this name in this scope."]);
+		assert!(diagnostics[0].render(src).ends_with("note: 'a' was first declared here\n --> line 2, column 7\n  |\n2 |   var a = 1;\n  |       ^\n"));
+	}
+
+	#[test]
+	fn diagnostics_duplicate_variable_note_on_tree() {
+		let src = "{\n  var a = 1;\n  var a = 2;\n}";
+		let diagnostics = resolve_errors(src);
+		assert_eq!(diagnostics[0].code, ErrorCode::DuplicateVariable);
+		assert!(diagnostics[0].render(src).ends_with("note: 'a' was first declared here\n --> line 2, column 7\n  |\n2 |   var a = 1;\n  |       ^\n"));
+	}
+
+	#[test]
+	fn diagnostics_const_note() {
+		let src = "{\n  const a = 1;\n  a = 2;\n}";
+		let diagnostics = compile_errors(src);
+		assert_eq!(diagnostics[0].code, ErrorCode::ReassignConstant);
+		assert_eq!(diagnostics[0].notes[0].message, "'a' was declared const here");
+	}
+
+	#[test]
+	fn diagnostics_scan_errors() {
+		let diagnostics = compile_errors("print 1 # 2;\nprint \"open;");
+		assert_eq!(diagnostics.iter().map(|diagnostic| diagnostic.code).collect::<Vec<_>>(), [ErrorCode::UnexpectedCharacter, ErrorCode::UnterminatedString]);
+		assert_eq!(diagnostics[0].column, 9);
+	}
+
+	#[test]
+	fn diagnostics_tree_walk_parser() {
+		let src = "1 = 2;\nprint 1 +;\nvar x = 2";
+		let diagnostics = parse_errors(src);
+		assert_eq!(diagnostics.iter().map(|diagnostic| (diagnostic.code, diagnostic.line, diagnostic.column)).collect::<Vec<_>>(), [
+			(ErrorCode::InvalidAssignmentTarget, 1, 3),
+			(ErrorCode::ExpectExpression, 2, 10),
+			(ErrorCode::ExpectToken, 3, 10)
+		]);
+		assert!(diagnostics[1].render(src).starts_with("error[E0003]: Expect expression.\n --> line 2, column 10\n"));
+	}
+}
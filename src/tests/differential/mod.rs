@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+	use std::{fs, path::Path};
+
+	use crate::{interpreter::Interpreter, output::OutputBuffer, tests::lox_files, virtual_machine::{InterpretResult, InterpreterOutput, VirtualMachine}};
+
+	//Files whose output is known to differ between the backends, with the reason
+	const KNOWN_DIVERGENCES: &[(&str, &str)] = &[
+		("for_loop/break.lox", "the tree-walker has no break or continue"),
+		("for_loop/break_closure.lox", "the tree-walker has no break or continue"),
+		("for_loop/break_in_switch.lox", "the tree-walker has no break or switch"),
+		("for_loop/continue.lox", "the tree-walker has no break or continue"),
+		("for_loop/nested_continue.lox", "the tree-walker has no break or continue"),
+		("limit/loop_too_large.lox", "jump offsets only limit the VM"),
+		("limit/too_many_locals.lox", "local slots only limit the VM"),
+		("limit/too_many_upvalues.lox", "upvalue slots only limit the VM"),
+		("string/interning.lox", "the tree-walker has no switch"),
+		("while_loop/break.lox", "the tree-walker has no break or continue"),
+		("while_loop/continue.lox", "the tree-walker has no break or continue"),
+		("while_loop/nested_break.lox", "the tree-walker has no break or continue"),
+	];
+
+	//What a backend printed before it stopped and how the run ended
+	#[derive(Debug, PartialEq)]
+	struct Outcome {
+		printed: Vec<String>,
+		result: InterpretResult
+	}
+
+	fn outcome(buffer: OutputBuffer, output: InterpreterOutput) -> Outcome {
+		return Outcome { printed: buffer.take(), result: output.result };
+	}
+
+	fn run_vm(src: &str) -> Outcome {
+		let buffer = OutputBuffer::default();
+		let mut vm = VirtualMachine::new();
+		vm.set_output(Box::new(buffer.clone()));
+		let output = vm.interpret_source(src.to_string(), false, false);
+		return outcome(buffer, output);
+	}
+
+	fn run_tree(src: &str) -> Outcome {
+		let buffer = OutputBuffer::default();
+		let mut interpreter = Interpreter::new();
+		interpreter.set_output(Box::new(buffer.clone()));
+		let output = interpreter.interpret_source(src.to_string());
+		return outcome(buffer, output);
+	}
+
+	#[test]
+	fn differential_backends_agree() {
+		let root = Path::new("src/tests");
+		let mut files = vec![];
+		lox_files(root, root, &mut files);
+		files.sort();
+
+		let mut divergences = vec![];
+		let mut fixed = vec![];
+		for file in files.iter() {
+			//The benchmarks take too long in a debug build
+			if file.starts_with("benchmark/") {
+				continue;
+			}
+			let src = fs::read_to_string(root.join(file)).expect("Error: file doesnt exist");
+			let vm = run_vm(&src);
+			let tree = run_tree(&src);
+			let known = KNOWN_DIVERGENCES.iter().any(|(known, _)| known == file);
+			if vm != tree && !known {
+				divergences.push(format!("{file}\n  vm:   {:?}\n  tree: {:?}", vm, tree));
+			} else if vm == tree && known {
+				fixed.push(file.clone());
+			}
+		}
+		assert!(divergences.is_empty(), "The backends disagree on:\n{}", divergences.join("\n"));
+		assert!(fixed.is_empty(), "These now agree and can be removed from KNOWN_DIVERGENCES: {:?}", fixed);
+	}
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+	use crate::{bytecode::Value, heap::Heap, native::Arity, output::OutputBuffer, virtual_machine::RuntimeError, Lox, LoxError};
+
+	#[test]
+	fn embedding_eval_and_get_global() {
+		let mut lox = Lox::new();
+		lox.eval("var answer = 6 * 7; var name = \"lox\"; var nothing;").unwrap();
+		assert_eq!(lox.get_global::<f64>("answer"), Ok(42.0));
+		assert_eq!(lox.get_global::<i64>("answer"), Ok(42));
+		assert_eq!(lox.get_global::<String>("name"), Ok(format!("lox")));
+		assert_eq!(lox.get_global::<Option<f64>>("nothing"), Ok(None));
+		assert_eq!(lox.get_global::<f64>("missing"), Err(LoxError::UndefinedGlobal(format!("missing"))));
+		assert_eq!(lox.get_global::<bool>("name"), Err(LoxError::Type(format!("Expected a bool but got a string."))));
+	}
+
+	#[test]
+	fn embedding_i64_out_of_range() {
+		let mut lox = Lox::new();
+		//2^63 is the first double past i64::MAX, a saturating cast would turn it into i64::MAX
+		lox.eval("var big = 9223372036854775808; var small = -9223372036854775808;").unwrap();
+		assert_eq!(lox.get_global::<i64>("big"), Err(LoxError::Type(format!("Expected a whole number but got a number."))));
+		assert_eq!(lox.get_global::<i64>("small"), Ok(i64::MIN));
+	}
+
+	#[test]
+	fn embedding_set_global() {
+		let mut lox = Lox::new();
+		let output = OutputBuffer::default();
+		lox.set_output(output.clone());
+		lox.set_global("width", 3);
+		lox.set_global("unit", "cm");
+		lox.eval("print str(width * 2) + unit;").unwrap();
+		assert_eq!(output.take(), ["6cm"]);
+	}
+
+	#[test]
+	fn embedding_call() {
+		let mut lox = Lox::new();
+		let output = OutputBuffer::default();
+		lox.set_output(output.clone());
+		lox.eval("fun greet(name, times) { var s = \"\"; for (var i = 0; i < times; i = i + 1) s = s + name; print s; return len(s); }").unwrap();
+		assert_eq!(lox.call::<f64>("greet", &[&"ab", &3]), Ok(6.0));
+		assert_eq!(output.take(), ["ababab"]);
+		assert_eq!(lox.call::<String>("str", &[&true]), Ok(format!("true")));
+	}
+
+	#[test]
+	fn embedding_call_class_and_closure() {
+		let mut lox = Lox::new();
+		let output = OutputBuffer::default();
+		lox.set_output(output.clone());
+		lox.eval("class Point { init(x) { this.x = x; } } fun counter() { var n = 0; fun next() { n = n + 1; return n; } return next; } var next = counter();").unwrap();
+		lox.call::<f64>("next", &[]).unwrap();
+		assert_eq!(lox.call::<f64>("next", &[]), Ok(2.0));
+		assert!(lox.call::<f64>("Point", &[&1]).is_err());
+		lox.eval("var p = Point(5); print p.x;").unwrap();
+		assert_eq!(output.take(), ["5"]);
+	}
+
+	#[test]
+	fn embedding_errors() {
+		let mut lox = Lox::new();
+		let output = OutputBuffer::default();
+		lox.set_output(output.clone());
+		assert_eq!(lox.eval("print ;"), Err(LoxError::Compile(vec![format!("[Line 1] Error at ';'"), format!("Error Message: Expect expression.")])));
+		lox.eval("fun fail(x) { return -x; }").unwrap();
+		assert_eq!(lox.call::<()>("fail", &[&"a"]), Err(LoxError::Runtime(vec![format!("[Line 1] Runtime Type Error"), format!("Error Message: Operand must be a number.")])));
+		assert_eq!(lox.call::<()>("fail", &[]), Err(LoxError::Runtime(vec![format!("Runtime Function Error"), format!("Error Message: Expected 1 arguments but got 0.")])));
+		assert_eq!(lox.call::<f64>("len", &[&1]), Err(LoxError::Runtime(vec![format!("Runtime Function Error"), format!("Error Message: len() expects a string.")])));
+		//The engine is still usable after an error
+		assert_eq!(lox.call::<f64>("fail", &[&2]), Ok(-2.0));
+		assert_eq!(lox.eval("print 1; exit(4);"), Err(LoxError::Exit(4)));
+		assert_eq!(output.take(), ["1"]);
+	}
+
+	fn native_double(_heap: &mut Heap, args: &[Value]) -> Result<Value, RuntimeError> {
+		match args[0] {
+			Value::Number(n) => Ok(Value::Number(n * 2.0)),
+			_ => Err(RuntimeError::FunctionError(format!("double() expects a number."), 0))
+		}
+	}
+
+	#[test]
+	fn embedding_define_native() {
+		let mut lox = Lox::new();
+		lox.define_native("double", Arity::Fixed(1), native_double);
+		lox.eval("var x = double(21);").unwrap();
+		assert_eq!(lox.get_global::<f64>("x"), Ok(42.0));
+	}
+}
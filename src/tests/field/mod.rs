@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::run_from_file;
+
+	#[test]
+	fn field_call_function_field() {
+		assert_eq!(run_from_file("src/tests/field/call_function_field.lox"), ["bar", "1", "2"]);
+	}
+
+	#[test]
+	fn field_call_nonfunction_field() {
+		assert_eq!(run_from_file("src/tests/field/call_nonfunction_field.lox"), ["[Line 6] Runtime Type Error", "Error Message: Can only call functions and classes."]);
+	}
+
+	#[test]
+	fn field_get_and_set_method() {
+		assert_eq!(run_from_file("src/tests/field/get_and_set_method.lox"), ["other", "1", "method", "2"]);
+	}
+
+	#[test]
+	fn field_get_on_bool() {
+		assert_eq!(run_from_file("src/tests/field/get_on_bool.lox"), ["[Line 1] Runtime Type Error", "Error Message: Only instances have properties."]);
+	}
+
+	#[test]
+	fn field_get_on_class() {
+		assert_eq!(run_from_file("src/tests/field/get_on_class.lox"), ["[Line 2] Runtime Type Error", "Error Message: Only instances have properties."]);
+	}
+
+	#[test]
+	fn field_get_on_function() {
+		assert_eq!(run_from_file("src/tests/field/get_on_function.lox"), ["[Line 3] Runtime Type Error", "Error Message: Only instances have properties."]);
+	}
+
+	#[test]
+	fn field_get_on_nil() {
+		assert_eq!(run_from_file("src/tests/field/get_on_nil.lox"), ["[Line 1] Runtime Type Error", "Error Message: Only instances have properties."]);
+	}
+
+	#[test]
+	fn field_get_on_num() {
+		assert_eq!(run_from_file("src/tests/field/get_on_num.lox"), ["[Line 1] Runtime Type Error", "Error Message: Only instances have properties."]);
+	}
+
+	#[test]
+	fn field_get_on_string() {
+		assert_eq!(run_from_file("src/tests/field/get_on_string.lox"), ["[Line 1] Runtime Type Error", "Error Message: Only instances have properties."]);
+	}
+
+	#[test]
+	fn field_many() {
+		assert_eq!(run_from_file("src/tests/field/many.lox"), ["apple", "apricot", "avocado", "banana", "bilberry", "blackberry", "blackcurrant", "blueberry", "boysenberry", "cantaloupe", "cherimoya", "cherry", "clementine", "cloudberry", "coconut", "cranberry", "currant", "damson", "date", "dragonfruit", "durian", "elderberry", "feijoa", "fig", "gooseberry", "grape", "grapefruit", "guava", "honeydew", "huckleberry", "jabuticaba", "jackfruit", "jambul", "jujube", "juniper", "kiwifruit", "kumquat", "lemon", "lime", "longan", "loquat", "lychee", "mandarine", "mango", "marionberry", "melon", "miracle", "mulberry", "nance", "nectarine", "olive", "orange", "papaya", "passionfruit", "peach", "pear", "persimmon", "physalis", "pineapple", "plantain", "plum", "plumcot", "pomegranate", "pomelo", "quince", "raisin", "rambutan", "raspberry", "redcurrant", "salak", "salmonberry", "satsuma", "strawberry", "tamarillo", "tamarind", "tangerine", "tomato", "watermelon", "yuzu"]);
+	}
+
+	#[test]
+	fn field_method() {
+		assert_eq!(run_from_file("src/tests/field/method.lox"), ["got method", "arg"]);
+	}
+
+	#[test]
+	fn field_method_binds_this() {
+		assert_eq!(run_from_file("src/tests/field/method_binds_this.lox"), ["foo1", "1"]);
+	}
+
+	#[test]
+	fn field_on_instance() {
+		assert_eq!(run_from_file("src/tests/field/on_instance.lox"), ["bar value", "baz value", "bar value", "baz value"]);
+	}
+
+	#[test]
+	fn field_set_evaluation_order() {
+		assert_eq!(run_from_file("src/tests/field/set_evaluation_order.lox"), ["[Line 1] Runtime Var Error", "Error Message: Undefined variable 'undefined1'."]);
+	}
+
+	#[test]
+	fn field_set_on_bool() {
+		assert_eq!(run_from_file("src/tests/field/set_on_bool.lox"), ["[Line 1] Runtime Type Error", "Error Message: Only instances have fields."]);
+	}
+
+	#[test]
+	fn field_set_on_class() {
+		assert_eq!(run_from_file("src/tests/field/set_on_class.lox"), ["[Line 2] Runtime Type Error", "Error Message: Only instances have fields."]);
+	}
+
+	#[test]
+	fn field_set_on_function() {
+		assert_eq!(run_from_file("src/tests/field/set_on_function.lox"), ["[Line 3] Runtime Type Error", "Error Message: Only instances have fields."]);
+	}
+
+	#[test]
+	fn field_set_on_nil() {
+		assert_eq!(run_from_file("src/tests/field/set_on_nil.lox"), ["[Line 1] Runtime Type Error", "Error Message: Only instances have fields."]);
+	}
+
+	#[test]
+	fn field_set_on_num() {
+		assert_eq!(run_from_file("src/tests/field/set_on_num.lox"), ["[Line 1] Runtime Type Error", "Error Message: Only instances have fields."]);
+	}
+
+	#[test]
+	fn field_set_on_string() {
+		assert_eq!(run_from_file("src/tests/field/set_on_string.lox"), ["[Line 1] Runtime Type Error", "Error Message: Only instances have fields."]);
+	}
+
+	#[test]
+	fn field_undefined() {
+		assert_eq!(run_from_file("src/tests/field/undefined.lox"), ["[Line 4] Runtime Var Error", "Error Message: Undefined property 'bar'."]);
+	}
+
+}
\ No newline at end of file
@@ -2,6 +2,21 @@
 mod tests {
 	use crate::tests::run_from_file;
 
+	#[test]
+	fn for_loop_break() {
+		assert_eq!(run_from_file("src/tests/for_loop/break.lox"), ["0", "2", "4", "once", "after"]);
+	}
+
+	#[test]
+	fn for_loop_break_closure() {
+		assert_eq!(run_from_file("src/tests/for_loop/break_closure.lox"), ["1"]);
+	}
+
+	#[test]
+	fn for_loop_break_in_switch() {
+		assert_eq!(run_from_file("src/tests/for_loop/break_in_switch.lox"), ["0", "2", "before", "after"]);
+	}
+
 	#[test]
 	fn for_loop_class_in_body() {
 		assert_eq!(run_from_file("src/tests/for_loop/class_in_body.lox"), ["[Line 1] Error at 'class'", "Error Message: Expect expression."]);
@@ -12,11 +27,21 @@ mod tests {
 		assert_eq!(run_from_file("src/tests/for_loop/closure_in_body.lox"), ["4", "1", "4", "2", "4", "3"]);
 	}
 
+	#[test]
+	fn for_loop_continue() {
+		assert_eq!(run_from_file("src/tests/for_loop/continue.lox"), ["0", "2", "4", "after"]);
+	}
+
 	#[test]
 	fn for_loop_fun_in_body() {
 		assert_eq!(run_from_file("src/tests/for_loop/fun_in_body.lox"), ["[Line 1] Error at 'fun'", "Error Message: Expect expression."]);
 	}
 
+	#[test]
+	fn for_loop_nested_continue() {
+		assert_eq!(run_from_file("src/tests/for_loop/nested_continue.lox"), ["0", "2", "1", "3", "outer"]);
+	}
+
 	#[test]
 	fn for_loop_return_closure() {
 		assert_eq!(run_from_file("src/tests/for_loop/return_closure.lox"), ["i"]);
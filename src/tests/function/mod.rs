@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::run_from_file;
+
+	#[test]
+	fn function_body_must_be_block() {
+		assert_eq!(run_from_file("src/tests/function/body_must_be_block.lox"), ["[Line 3] Error at '123'", "Error Message: Expect '{' before function body.", "[Line 4] Error at 'EOF'", "Error Message: Expect '}' after block."]);
+	}
+
+	#[test]
+	fn function_empty_body() {
+		assert_eq!(run_from_file("src/tests/function/empty_body.lox"), ["nil"]);
+	}
+
+	#[test]
+	fn function_extra_arguments() {
+		assert_eq!(run_from_file("src/tests/function/extra_arguments.lox"), ["[Line 6] Runtime Function Error", "Error Message: Expected 2 arguments but got 4."]);
+	}
+
+	#[test]
+	fn function_local_mutual_recursion() {
+		assert_eq!(run_from_file("src/tests/function/local_mutual_recursion.lox"), ["[Line 4] Runtime Var Error", "Error Message: Undefined variable 'isOdd'."]);
+	}
+
+	#[test]
+	fn function_local_recursion() {
+		assert_eq!(run_from_file("src/tests/function/local_recursion.lox"), ["21"]);
+	}
+
+	#[test]
+	fn function_missing_arguments() {
+		assert_eq!(run_from_file("src/tests/function/missing_arguments.lox"), ["[Line 3] Runtime Function Error", "Error Message: Expected 2 arguments but got 1."]);
+	}
+
+	#[test]
+	fn function_missing_comma_in_parameters() {
+		assert_eq!(run_from_file("src/tests/function/missing_comma_in_parameters.lox"), ["[Line 3] Error at 'c'", "Error Message: Expect ')' after parameters.", "[Line 4] Error at 'EOF'", "Error Message: Expect '}' after block."]);
+	}
+
+	#[test]
+	fn function_mutual_recursion() {
+		assert_eq!(run_from_file("src/tests/function/mutual_recursion.lox"), ["true", "true"]);
+	}
+
+	#[test]
+	fn function_nested_call_with_arguments() {
+		assert_eq!(run_from_file("src/tests/function/nested_call_with_arguments.lox"), ["hello world"]);
+	}
+
+	#[test]
+	fn function_parameters() {
+		assert_eq!(run_from_file("src/tests/function/parameters.lox"), ["0", "1", "3", "6", "10", "15", "21", "28", "36"]);
+	}
+
+	#[test]
+	fn function_recursion() {
+		assert_eq!(run_from_file("src/tests/function/recursion.lox"), ["21"]);
+	}
+
+	#[test]
+	fn function_too_many_arguments() {
+		assert_eq!(run_from_file("src/tests/function/too_many_arguments.lox"), ["[Line 260] Error at 'a'", "Error Message: Can't have more than 255 arguments."]);
+	}
+
+	#[test]
+	fn function_too_many_parameters() {
+		assert_eq!(run_from_file("src/tests/function/too_many_parameters.lox"), ["[Line 257] Error at 'a'", "Error Message: Can't have more than 255 parameters."]);
+	}
+
+}
@@ -0,0 +1,25 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::run_from_file;
+
+	#[test]
+	fn gc_bound_method() {
+		assert_eq!(run_from_file("src/tests/gc/bound_method.lox"), ["hello world"]);
+	}
+
+	#[test]
+	fn gc_closure_survives() {
+		assert_eq!(run_from_file("src/tests/gc/closure_survives.lox"), ["1", "2"]);
+	}
+
+	#[test]
+	fn gc_instance_cycle() {
+		assert_eq!(run_from_file("src/tests/gc/instance_cycle.lox"), ["b", "a"]);
+	}
+
+	#[test]
+	fn gc_string_garbage() {
+		assert_eq!(run_from_file("src/tests/gc/string_garbage.lox"), ["kept", "kept!"]);
+	}
+
+}
\ No newline at end of file
@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::{run_from_file, run_from_file_on_tree};
+
+	#[test]
+	fn inheritance_constructor() {
+		assert_eq!(run_from_file("src/tests/inheritance/constructor.lox"), ["value"]);
+		assert_eq!(run_from_file_on_tree("src/tests/inheritance/constructor.lox"), ["value"]);
+	}
+
+	#[test]
+	fn inheritance_inherit_from_function() {
+		assert_eq!(run_from_file("src/tests/inheritance/inherit_from_function.lox"), ["[Line 3] Runtime Type Error", "Error Message: Superclass must be a class."]);
+	}
+
+	#[test]
+	fn inheritance_inherit_from_nil() {
+		assert_eq!(run_from_file("src/tests/inheritance/inherit_from_nil.lox"), ["[Line 2] Runtime Type Error", "Error Message: Superclass must be a class."]);
+	}
+
+	#[test]
+	fn inheritance_inherit_from_number() {
+		assert_eq!(run_from_file("src/tests/inheritance/inherit_from_number.lox"), ["[Line 2] Runtime Type Error", "Error Message: Superclass must be a class."]);
+	}
+
+	#[test]
+	fn inheritance_inherit_methods() {
+		assert_eq!(run_from_file("src/tests/inheritance/inherit_methods.lox"), ["foo", "bar", "bar"]);
+		assert_eq!(run_from_file_on_tree("src/tests/inheritance/inherit_methods.lox"), ["foo", "bar", "bar"]);
+	}
+
+	#[test]
+	fn inheritance_parenthesized_superclass() {
+		assert_eq!(run_from_file("src/tests/inheritance/parenthesized_superclass.lox"), ["[Line 3] Error at '('", "Error Message: Expect superclass name."]);
+	}
+
+	#[test]
+	fn inheritance_set_fields_from_base_class() {
+		assert_eq!(run_from_file("src/tests/inheritance/set_fields_from_base_class.lox"), ["foo 1", "foo 2", "bar 1", "bar 2", "bar 1", "bar 2"]);
+		assert_eq!(run_from_file_on_tree("src/tests/inheritance/set_fields_from_base_class.lox"), ["foo 1", "foo 2", "bar 1", "bar 2", "bar 1", "bar 2"]);
+	}
+
+}
\ No newline at end of file
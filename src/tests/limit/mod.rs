@@ -0,0 +1,32 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::{run_from_file, run_from_file_on_tree};
+
+	#[test]
+	fn limit_constant_long() {
+		assert_eq!(run_from_file("src/tests/limit/constant_long.lox"), ["299.5", "long"]);
+	}
+
+	#[test]
+	fn limit_loop_too_large() {
+		assert_eq!(run_from_file("src/tests/limit/loop_too_large.lox"), ["[Line 2351] Error at '}'", "Error Message: Loop body too large."]);
+	}
+
+	#[test]
+	fn limit_stack_overflow() {
+		let expected = ["[Line 18] Runtime Function Error", "Error Message: Stack overflow."];
+		assert_eq!(run_from_file("src/tests/limit/stack_overflow.lox"), expected);
+		assert_eq!(run_from_file_on_tree("src/tests/limit/stack_overflow.lox"), expected);
+	}
+
+	#[test]
+	fn limit_too_many_locals() {
+		assert_eq!(run_from_file("src/tests/limit/too_many_locals.lox"), ["[Line 52] Error at 'oops'", "Error Message: Too many local variables in function."]);
+	}
+
+	#[test]
+	fn limit_too_many_upvalues() {
+		assert_eq!(run_from_file("src/tests/limit/too_many_upvalues.lox"), ["[Line 102] Error at 'oops'", "Error Message: Too many closure variables in function."]);
+	}
+
+}
\ No newline at end of file
@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::{run_from_file, run_from_file_on_tree};
+
+	#[test]
+	fn method_arity() {
+		assert_eq!(run_from_file("src/tests/method/arity.lox"), ["no args", "1", "3", "6", "10", "15", "21", "28", "36"]);
+		assert_eq!(run_from_file_on_tree("src/tests/method/arity.lox"), ["no args", "1", "3", "6", "10", "15", "21", "28", "36"]);
+	}
+
+	#[test]
+	fn method_empty_block() {
+		assert_eq!(run_from_file("src/tests/method/empty_block.lox"), ["nil"]);
+	}
+
+	#[test]
+	fn method_extra_arguments() {
+		assert_eq!(run_from_file("src/tests/method/extra_arguments.lox"), ["[Line 8] Runtime Function Error", "Error Message: Expected 2 arguments but got 4."]);
+	}
+
+	#[test]
+	fn method_missing_arguments() {
+		assert_eq!(run_from_file("src/tests/method/missing_arguments.lox"), ["[Line 5] Runtime Function Error", "Error Message: Expected 2 arguments but got 1."]);
+	}
+
+	#[test]
+	fn method_not_found() {
+		assert_eq!(run_from_file("src/tests/method/not_found.lox"), ["[Line 3] Runtime Var Error", "Error Message: Undefined property 'unknown'."]);
+	}
+
+	#[test]
+	fn method_print_bound_method() {
+		assert_eq!(run_from_file("src/tests/method/print_bound_method.lox"), ["<fn method>"]);
+	}
+
+	#[test]
+	fn method_refer_to_name() {
+		assert_eq!(run_from_file("src/tests/method/refer_to_name.lox"), ["[Line 3] Runtime Var Error", "Error Message: Undefined variable 'method'."]);
+	}
+
+	#[test]
+	fn method_too_many_arguments() {
+		assert_eq!(run_from_file("src/tests/method/too_many_arguments.lox"), ["[Line 259] Error at 'a'", "Error Message: Can't have more than 255 arguments."]);
+	}
+
+	#[test]
+	fn method_too_many_parameters() {
+		assert_eq!(run_from_file("src/tests/method/too_many_parameters.lox"), ["[Line 258] Error at 'a'", "Error Message: Can't have more than 255 parameters."]);
+	}
+
+}
\ No newline at end of file
@@ -1,6 +1,6 @@
-use std::fs;
+use std::{fs, path::Path};
 
-use crate::virtual_machine::{interpret_vm};
+use crate::{interpreter::Interpreter, output::OutputBuffer, virtual_machine::{InterpretResult, InterpreterOutput, VirtualMachine}};
 
 mod assignment;
 mod block;
@@ -13,8 +13,74 @@ mod if_statement;
 mod logical_operator;
 mod nil;
 mod number;
+mod function;
+mod return_statement;
+mod closure;
+mod class;
+mod constructor;
+mod field;
+mod inheritance;
+mod method;
+mod superclass;
+mod this;
+mod gc;
+mod string;
+mod limit;
+mod bytecode_file;
+mod repl;
+mod native;
+mod embedding;
+mod output;
+mod diagnostics;
+mod stack_trace;
+mod resolver;
+mod references;
+mod runtime_error;
+mod differential;
+mod bench;
 
 fn run_from_file(file: &str) -> Vec<String> {
-    let result = interpret_vm(fs::read_to_string(file.to_string()).expect("Error: file doesnt exist"), false);  
-    return result.output
-}
\ No newline at end of file
+    let src = fs::read_to_string(file.to_string()).expect("Error: file doesnt exist");
+    return run_on_vm(|vm| vm.interpret_source(src, false, false));
+}
+
+//The printed lines, or only the error lines if the script failed
+fn run_on_vm(run: impl FnOnce(&mut VirtualMachine) -> InterpreterOutput) -> Vec<String> {
+    let buffer = OutputBuffer::default();
+    let mut vm = VirtualMachine::new();
+    vm.set_output(Box::new(buffer.clone()));
+    let result = run(&mut vm);
+    match result.result {
+        InterpretResult::InterpretOk | InterpretResult::InterpretExit(_) => buffer.take(),
+        _ => result.errors
+    }
+}
+
+//The same as run_on_vm for the tree-walk interpreter, so one expectation can check both backends
+fn run_on_tree(src: &str) -> Vec<String> {
+    let buffer = OutputBuffer::default();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Box::new(buffer.clone()));
+    let result = interpreter.interpret_source(src.to_string());
+    match result.result {
+        InterpretResult::InterpretOk | InterpretResult::InterpretExit(_) => buffer.take(),
+        _ => result.errors
+    }
+}
+
+fn run_from_file_on_tree(file: &str) -> Vec<String> {
+    let src = fs::read_to_string(file.to_string()).expect("Error: file doesnt exist");
+    return run_on_tree(&src);
+}
+
+//Every .lox file under dir, as paths relative to root
+fn lox_files(root: &Path, dir: &Path, files: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).expect("Error: can't read test directory") {
+        let path = entry.expect("Error: can't read test directory").path();
+        if path.is_dir() {
+            lox_files(root, &path, files);
+        } else if path.extension().is_some_and(|extension| extension == "lox") {
+            files.push(path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"));
+        }
+    }
+}
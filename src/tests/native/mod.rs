@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::{run_from_file, run_from_file_on_tree};
+
+	#[test]
+	fn native_arity() {
+		assert_eq!(run_from_file("src/tests/native/arity.lox"), ["[Line 1] Runtime Function Error", "Error Message: Expected 0 arguments but got 1."]);
+	}
+
+	#[test]
+	fn native_clock() {
+		assert_eq!(run_from_file("src/tests/native/clock.lox"), ["true", "number"]);
+	}
+
+	#[test]
+	fn native_exit() {
+		assert_eq!(run_from_file("src/tests/native/exit.lox"), ["before"]);
+	}
+
+	#[test]
+	fn native_input_arity() {
+		let expected = ["[Line 1] Runtime Function Error", "Error Message: Expected at most 1 argument but got 2."];
+		assert_eq!(run_from_file("src/tests/native/input_arity.lox"), expected);
+		assert_eq!(run_from_file_on_tree("src/tests/native/input_arity.lox"), expected);
+	}
+
+	#[test]
+	fn native_len() {
+		assert_eq!(run_from_file("src/tests/native/len.lox"), ["0", "3", "4"]);
+	}
+
+	#[test]
+	fn native_len_not_string() {
+		assert_eq!(run_from_file("src/tests/native/len_not_string.lox"), ["[Line 1] Runtime Function Error", "Error Message: len() expects a string."]);
+	}
+
+	#[test]
+	fn native_num() {
+		assert_eq!(run_from_file("src/tests/native/num.lox"), ["43", "-2.5", "7"]);
+	}
+
+	#[test]
+	fn native_num_invalid() {
+		assert_eq!(run_from_file("src/tests/native/num_invalid.lox"), ["[Line 1] Runtime Function Error", "Error Message: Can't convert 'seven' to a number."]);
+	}
+
+	#[test]
+	fn native_print() {
+		assert_eq!(run_from_file("src/tests/native/print.lox"), ["<native fn>"]);
+	}
+
+	#[test]
+	fn native_shadow() {
+		assert_eq!(run_from_file("src/tests/native/shadow.lox"), ["mine"]);
+	}
+
+	#[test]
+	fn native_str() {
+		assert_eq!(run_from_file("src/tests/native/str.lox"), ["1.5x", "true", "nil", "true", "Foo instance"]);
+	}
+
+	#[test]
+	fn native_type() {
+		assert_eq!(run_from_file("src/tests/native/type.lox"), ["number", "string", "bool", "nil", "function", "function", "class", "instance", "function"]);
+	}
+
+}
\ No newline at end of file
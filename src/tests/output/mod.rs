@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+	use std::{cell::RefCell, env, fs::{self, File}, rc::Rc};
+
+	use crate::{interpreter::Interpreter, output::{OutputBuffer, OutputSink, WriteSink}, runner::run_tree_walk, virtual_machine::{InterpretResult, VirtualMachine}};
+
+	#[test]
+	fn output_streams_before_runtime_error() {
+		let buffer = OutputBuffer::default();
+		let mut vm = VirtualMachine::new();
+		vm.set_output(Box::new(buffer.clone()));
+		let result = vm.interpret_source(format!("print 1; print \"two\"; print -nil;"), false, false);
+		assert!(matches!(result.result, InterpretResult::InterpretRuntimeError));
+		assert_eq!(buffer.lines(), ["1", "two"]);
+		assert_eq!(result.errors, ["[Line 1] Runtime Type Error", "Error Message: Operand must be a number."]);
+	}
+
+	#[test]
+	fn output_callback() {
+		//Each line reaches the callback while the script is still running
+		let seen = Rc::new(RefCell::new(vec![]));
+		let log = seen.clone();
+		let mut vm = VirtualMachine::new();
+		vm.set_output(Box::new(move |line: &str| log.borrow_mut().push(format!("{}!", line))));
+		vm.interpret_source(format!("for (var i = 0; i < 3; i = i + 1) print i;"), false, false);
+		assert_eq!(*seen.borrow(), ["0!", "1!", "2!"]);
+	}
+
+	#[test]
+	fn output_write_sink() {
+		let path = env::temp_dir().join("lox_output_write_sink.txt");
+		{
+			let mut vm = VirtualMachine::new();
+			vm.set_output(Box::new(WriteSink(File::create(&path).unwrap())));
+			vm.interpret_source(format!("print \"a\"; print 2;"), false, false);
+		}
+		assert_eq!(fs::read_to_string(&path).unwrap(), "a\n2\n");
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn output_write_sink_prompt() {
+		//The prompt stays on the line the answer is typed on
+		let mut sink = WriteSink(vec![]);
+		sink.write_prompt("name? ");
+		sink.write_line("done");
+		assert_eq!(sink.0, b"name? done\n");
+	}
+
+	#[test]
+	fn output_tree_walk() {
+		let buffer = OutputBuffer::default();
+		let mut interpreter = Interpreter::new();
+		interpreter.set_output(Box::new(buffer.clone()));
+		let result = run_tree_walk(&mut interpreter, format!("var a = \"x\"; print a; print 1 + 2;"), false);
+		assert!(matches!(result, InterpretResult::InterpretOk));
+		assert_eq!(buffer.take(), ["x", "3"]);
+	}
+}
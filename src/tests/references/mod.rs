@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+	use crate::{interpreter::Interpreter, output::OutputBuffer, runner::run_tree_walk, tests::run_on_tree, virtual_machine::InterpretResult};
+
+	#[test]
+	fn references_instance_aliases() {
+		assert_eq!(run_on_tree("class Box {} var a = Box(); var b = a; b.value = 1; print a.value;"), ["1"]);
+	}
+
+	#[test]
+	fn references_instance_passed_to_function() {
+		assert_eq!(run_on_tree("class Box {} fun fill(box) { box.value = \"full\"; } var box = Box(); fill(box); print box.value;"), ["full"]);
+	}
+
+	#[test]
+	fn references_instance_identity() {
+		assert_eq!(run_on_tree("class A {} var a = A(); var b = a; print a == b; print a == A(); print a != A();"), ["true", "false", "true"]);
+	}
+
+	#[test]
+	fn references_field_on_expression() {
+		assert_eq!(run_on_tree("class Node {} var a = Node(); a.next = Node(); a.next.value = 3; print a.next.value;"), ["3"]);
+	}
+
+	#[test]
+	fn references_cycle() {
+		assert_eq!(run_on_tree("class Node {} var a = Node(); a.self = a; a.value = 1; print a.self.self.value;"), ["1"]);
+	}
+
+	#[test]
+	fn references_block_assigns_outer_local() {
+		assert_eq!(run_on_tree("{ var x = 1; { x = x + 1; { x = x * 10; } } print x; }"), ["20"]);
+	}
+
+	#[test]
+	fn references_globals_after_runtime_error() {
+		//The failed block is left, so the next script runs against the globals again
+		let buffer = OutputBuffer::default();
+		let mut interpreter = Interpreter::new();
+		interpreter.set_output(Box::new(buffer.clone()));
+		let result = run_tree_walk(&mut interpreter, format!("var a = 1; {{ var a = 2; {{ a = -nil; }} }}"), false);
+		assert!(matches!(result, InterpretResult::InterpretRuntimeError));
+		run_tree_walk(&mut interpreter, format!("print a;"), false);
+		assert_eq!(buffer.take(), ["1"]);
+	}
+}
@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+	use crate::{output::OutputBuffer, repl::is_incomplete, virtual_machine::{InterpretResult, VirtualMachine}};
+
+	fn run_lines(lines: &[&str]) -> Vec<Vec<String>> {
+		let buffer = OutputBuffer::default();
+		let mut vm = VirtualMachine::new();
+		vm.set_output(Box::new(buffer.clone()));
+		return lines.iter().map(|line| {
+			let result = vm.interpret_repl(line.to_string(), false, false);
+			match result.result {
+				InterpretResult::InterpretOk => buffer.take(),
+				_ => result.errors
+			}
+		}).collect();
+	}
+
+	#[test]
+	fn repl_globals_persist() {
+		let output = run_lines(&["var a = 1;", "fun add(b) { return a + b; }", "print add(2);", "a = 10;", "print add(2);"]);
+		assert_eq!(output, [vec![], vec![], vec!["3"], vec!["10"], vec!["12"]]);
+	}
+
+	#[test]
+	fn repl_prints_expressions() {
+		let output = run_lines(&["1 + 2;", "\"no semicolon\"", "var a = 3; a * 2; print a;", "{ a; }"]);
+		assert_eq!(output, [vec!["3"], vec!["no semicolon"], vec!["6", "3"], vec![]]);
+	}
+
+	#[test]
+	fn repl_recovers_from_runtime_error() {
+		let output = run_lines(&["var a = \"kept\";", "fun fail() { return -a; }", "fail();", "a"]);
+		assert_eq!(output[2], ["[Line 1] Runtime Type Error", "Error Message: Operand must be a number."]);
+		assert_eq!(output[3], ["kept"]);
+	}
+
+	#[test]
+	fn repl_closure_survives_runtime_error() {
+		let output = run_lines(&["var f; { var p = 1; var q = 2; var r = 3; var x = \"captured\"; fun g() { print x; } f = g; nil.foo; }", "f();"]);
+		assert_eq!(output[0], ["[Line 1] Runtime Type Error", "Error Message: Only instances have properties."]);
+		assert_eq!(output[1], ["captured", "nil"]);
+	}
+
+	#[test]
+	fn repl_recovers_from_compile_error() {
+		let output = run_lines(&["var a = 1;", "var = ;", "a"]);
+		assert_eq!(output[1][0], "[Line 1] Error at '='");
+		assert_eq!(output[2], ["1"]);
+	}
+
+	#[test]
+	fn repl_incomplete_input() {
+		assert!(is_incomplete("fun f() {"));
+		assert!(is_incomplete("print (1 +"));
+		assert!(is_incomplete("{ { }"));
+		assert!(is_incomplete("print \"open"));
+		assert!(!is_incomplete("fun f() { return 1; }"));
+		assert!(!is_incomplete("print \"{(\";"));
+		assert!(!is_incomplete("print 1; // {"));
+		assert!(!is_incomplete("}"));
+	}
+}
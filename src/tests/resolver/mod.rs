@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+	use crate::{diagnostics::ErrorCode, expr::Expr, interpreter::Interpreter, output::OutputBuffer, parser, resolver::resolve, runner::run_tree_walk, scanner::Scanner, stmt::Stmt, tests::run_on_tree, virtual_machine::InterpretResult};
+
+	fn resolve_source(src: &str) -> Result<Vec<Stmt>, Vec<ErrorCode>> {
+		let mut scanner = Scanner::default();
+		scanner.scan_tokens(src.to_string());
+		let mut statements = parser::parse(scanner.tokens).expect("Error: source doesnt parse");
+		return match resolve(&mut statements) {
+			Ok(()) => Ok(statements),
+			Err(diagnostics) => Err(diagnostics.iter().map(|diagnostic| diagnostic.code).collect())
+		};
+	}
+
+	#[test]
+	fn resolver_depths() {
+		let statements = resolve_source("var a; { var b; { print b; print a; } }").unwrap();
+		let Stmt::Block(outer) = &statements[1] else { panic!("Error: expected a block") };
+		let Stmt::Block(inner) = &outer[1] else { panic!("Error: expected a block") };
+		assert!(matches!(&inner[0], Stmt::Print(Expr::Variable(_, Some(1)))));
+		assert!(matches!(&inner[1], Stmt::Print(Expr::Variable(_, None))));
+	}
+
+	#[test]
+	fn resolver_local_shadows_global() {
+		assert_eq!(run_on_tree("var a = \"global\"; { var a = \"local\"; { a = \"changed\"; } print a; } print a;"), ["changed", "global"]);
+	}
+
+	#[test]
+	fn resolver_global_assigned_from_function() {
+		assert_eq!(run_on_tree("var b = 1; fun set() { b = 2; } set(); print b;"), ["2"]);
+	}
+
+	#[test]
+	fn resolver_declarations_in_blocks() {
+		assert_eq!(run_on_tree("{ fun f() { return \"f\"; } print f(); } fun f() { return \"global\"; } print f();"), ["f", "global"]);
+	}
+
+	#[test]
+	fn resolver_own_initializer() {
+		assert_eq!(resolve_source("{ var a = 1; { var a = a; } }").unwrap_err(), [ErrorCode::ReadInOwnInitializer]);
+		//Globals can be redefined in terms of themselves
+		assert!(resolve_source("var a = 1; var a = a;").is_ok());
+	}
+
+	#[test]
+	fn resolver_return_outside_function() {
+		assert_eq!(resolve_source("return 1;").unwrap_err(), [ErrorCode::ReturnFromTopLevel]);
+	}
+
+	#[test]
+	fn resolver_this_outside_class() {
+		assert_eq!(resolve_source("print this; fun f() { print this; }").unwrap_err(), [ErrorCode::ThisOutsideClass, ErrorCode::ThisOutsideClass]);
+		assert!(resolve_source("class A { m() { print this; } }").is_ok());
+	}
+
+	#[test]
+	fn resolver_return_value_from_initializer() {
+		assert_eq!(resolve_source("class A { init() { return 1; } }").unwrap_err(), [ErrorCode::ReturnValueFromInitializer]);
+		assert!(resolve_source("class A { init() { return; } method() { return 1; } }").is_ok());
+	}
+
+	#[test]
+	fn resolver_this_depth() {
+		//Method bodies are one scope inside the environment binding 'this'
+		let statements = resolve_source("class A { m() { print this; } }").unwrap();
+		let Stmt::Class(_, _, methods) = &statements[0] else { panic!("Error: expected a class") };
+		let Stmt::Function(_, _, body) = &methods[0] else { panic!("Error: expected a method") };
+		assert!(matches!(&body[0], Stmt::Print(Expr::This(_, Some(1)))));
+	}
+
+	#[test]
+	fn resolver_invalid_super() {
+		assert_eq!(resolve_source("print super.a; class A { m() { super.m(); } }").unwrap_err(), [ErrorCode::InvalidSuper, ErrorCode::InvalidSuper]);
+		assert!(resolve_source("class A {} class B < A { m() { fun f() { return super.m; } } }").is_ok());
+	}
+
+	#[test]
+	fn resolver_duplicate_local() {
+		assert_eq!(resolve_source("fun f(a, a) {} { var b; var b; }").unwrap_err(), [ErrorCode::DuplicateVariable, ErrorCode::DuplicateVariable]);
+	}
+
+	#[test]
+	fn resolver_errors_stop_tree_walk() {
+		let buffer = OutputBuffer::default();
+		let mut interpreter = Interpreter::new();
+		interpreter.set_output(Box::new(buffer.clone()));
+		let result = run_tree_walk(&mut interpreter, format!("print 1; return;"), false);
+		assert!(matches!(result, InterpretResult::InterpretCompilerError));
+		assert!(buffer.take().is_empty());
+	}
+}
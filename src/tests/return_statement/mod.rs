@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::run_from_file;
+
+	#[test]
+	fn return_statement_after_else() {
+		assert_eq!(run_from_file("src/tests/return_statement/after_else.lox"), ["ok"]);
+	}
+
+	#[test]
+	fn return_statement_after_if() {
+		assert_eq!(run_from_file("src/tests/return_statement/after_if.lox"), ["ok"]);
+	}
+
+	#[test]
+	fn return_statement_after_while() {
+		assert_eq!(run_from_file("src/tests/return_statement/after_while.lox"), ["ok"]);
+	}
+
+	#[test]
+	fn return_statement_at_top_level() {
+		assert_eq!(run_from_file("src/tests/return_statement/at_top_level.lox"), ["[Line 1] Error at 'return'", "Error Message: Can't return from top-level code."]);
+	}
+
+	#[test]
+	fn return_statement_in_function() {
+		assert_eq!(run_from_file("src/tests/return_statement/in_function.lox"), ["ok"]);
+	}
+
+	#[test]
+	fn return_statement_in_method() {
+		assert_eq!(run_from_file("src/tests/return_statement/in_method.lox"), ["ok"]);
+	}
+
+	#[test]
+	fn return_statement_return_nil_if_no_value() {
+		assert_eq!(run_from_file("src/tests/return_statement/return_nil_if_no_value.lox"), ["nil"]);
+	}
+
+}
\ No newline at end of file
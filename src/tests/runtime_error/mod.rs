@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+	use crate::{interpreter::Interpreter, output::OutputBuffer, tests::{run_on_tree, run_on_vm}, virtual_machine::InterpretResult};
+
+	//Runs the script on both backends and checks they agree before returning what they printed or reported
+	fn run_both(src: &str) -> Vec<String> {
+		let tree = run_on_tree(src);
+		assert_eq!(tree, run_on_vm(|vm| vm.interpret_source(src.to_string(), false, false)));
+		return tree;
+	}
+
+	#[test]
+	fn runtime_error_operands() {
+		assert_eq!(run_both("print 1;\nprint 1 + nil;"), ["[Line 2] Runtime Type Error", "Error Message: Operand must be either both string or number."]);
+		assert_eq!(run_both("print \"a\" < 1;"), ["[Line 1] Runtime Type Error", "Error Message: Operand must be both number."]);
+		assert_eq!(run_both("\n\nprint -\"a\";"), ["[Line 3] Runtime Type Error", "Error Message: Operand must be a number."]);
+	}
+
+	#[test]
+	fn runtime_error_undefined_variable() {
+		assert_eq!(run_both("var a;\nprint b;"), ["[Line 2] Runtime Var Error", "Error Message: Undefined variable 'b'."]);
+		assert_eq!(run_both("\nb = 1;"), ["[Line 2] Runtime Var Error", "Error Message: Undefined variable 'b'."]);
+	}
+
+	#[test]
+	fn runtime_error_calls() {
+		assert_eq!(run_both("var a = 1;\na();"), ["[Line 2] Runtime Type Error", "Error Message: Can only call functions and classes."]);
+		assert_eq!(run_both("fun f(a, b) {}\nf(1);"), ["[Line 2] Runtime Function Error", "Error Message: Expected 2 arguments but got 1."]);
+		assert_eq!(run_both("class A { init(a) {} }\nA();"), ["[Line 2] Runtime Function Error", "Error Message: Expected 1 arguments but got 0."]);
+		//Natives report the line they were called from
+		assert_eq!(run_both("\nnum(\"x\");"), ["[Line 2] Runtime Function Error", "Error Message: Can't convert 'x' to a number."]);
+	}
+
+	#[test]
+	fn runtime_error_instances() {
+		assert_eq!(run_both("class A {}\nprint A().b;"), ["[Line 2] Runtime Var Error", "Error Message: Undefined property 'b'."]);
+		assert_eq!(run_both("var a = 1;\nprint a.b;"), ["[Line 2] Runtime Type Error", "Error Message: Only instances have properties."]);
+		assert_eq!(run_both("var a = 1;\na.b = 2;"), ["[Line 2] Runtime Type Error", "Error Message: Only instances have fields."]);
+		assert_eq!(run_both("var A = 1;\nclass B < A {}"), ["[Line 2] Runtime Type Error", "Error Message: Superclass must be a class."]);
+	}
+
+	#[test]
+	fn runtime_error_inside_function_stops_script() {
+		let src = "fun f() {\n  print 1;\n  -nil;\n  print 2;\n}\nclass A { init() { f(); } }\nA();\nprint 3;";
+		assert_eq!(run_both(src), ["[Line 3] Runtime Type Error", "Error Message: Operand must be a number."]);
+
+		let buffer = OutputBuffer::default();
+		let mut interpreter = Interpreter::new();
+		interpreter.set_output(Box::new(buffer.clone()));
+		let output = interpreter.interpret_source(src.to_string());
+		assert!(matches!(output.result, InterpretResult::InterpretRuntimeError));
+		assert_eq!(buffer.take(), ["1"]);
+	}
+
+	#[test]
+	fn runtime_error_not_raised_for_lox_semantics() {
+		//Only nil and false are falsey, and values of different types are just unequal
+		assert_eq!(run_both("if (nil) print 1; else print 2;\nif (0) print 3;\nprint nil or \"a\";\nprint 1 == \"1\";\nprint nil != false;"), ["2", "3", "a", "false", "true"]);
+	}
+}
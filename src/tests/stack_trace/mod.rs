@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+	use crate::{output::OutputBuffer, virtual_machine::{InterpretResult, InterpreterOutput, TraceFrame, VirtualMachine}};
+
+	fn run(src: &str) -> InterpreterOutput {
+		let mut vm = VirtualMachine::new();
+		vm.set_output(Box::new(OutputBuffer::default()));
+		return vm.interpret_source(src.to_string(), false, false);
+	}
+
+	fn frame(function: Option<&str>, line: usize) -> TraceFrame {
+		return TraceFrame { function: function.map(|name| name.to_string()), line };
+	}
+
+	#[test]
+	fn stack_trace_nested_calls() {
+		let output = run("fun fib(n) {\n  if (n < 2) return -nil;\n  return fib(n - 1) + fib(n - 2);\n}\n\nprint fib(2);");
+		assert!(matches!(output.result, InterpretResult::InterpretRuntimeError));
+		assert_eq!(output.errors, ["[Line 2] Runtime Type Error", "Error Message: Operand must be a number."]);
+		assert_eq!(output.stack_trace, [frame(Some("fib"), 2), frame(Some("fib"), 3), frame(None, 6)]);
+		assert_eq!(output.stack_trace.iter().map(|frame| frame.to_string()).collect::<Vec<_>>(), ["[line 2] in fib()", "[line 3] in fib()", "[line 6] in script"]);
+	}
+
+	#[test]
+	fn stack_trace_methods_and_natives() {
+		//Natives don't get a frame, the trace starts at the function that called them
+		let output = run("class Parser {\n  init(text) {\n    this.value = num(text);\n  }\n}\nParser(\"x\");");
+		assert_eq!(output.errors, ["[Line 3] Runtime Function Error", "Error Message: Can't convert 'x' to a number."]);
+		assert_eq!(output.stack_trace, [frame(Some("init"), 3), frame(None, 6)]);
+	}
+
+	#[test]
+	fn stack_trace_top_level() {
+		let output = run("print 1;\nprint undefined;");
+		assert_eq!(output.stack_trace, [frame(None, 2)]);
+	}
+
+	#[test]
+	fn stack_trace_empty_without_runtime_error() {
+		assert!(run("fun f() { return 1; } print f();").stack_trace.is_empty());
+		assert!(run("print ;").stack_trace.is_empty());
+		assert!(run("exit(3);").stack_trace.is_empty());
+	}
+
+	#[test]
+	fn stack_trace_call_from_rust() {
+		let mut vm = VirtualMachine::new();
+		vm.interpret_source(format!("fun outer() {{\n  inner();\n}}\nfun inner() {{\n  return -\"a\";\n}}"), false, false);
+		let outer = vm.get_global("outer").unwrap();
+		let output = vm.call_function(outer, &[]).err().unwrap();
+		assert_eq!(output.stack_trace, [frame(Some("inner"), 5), frame(Some("outer"), 2)]);
+	}
+}
@@ -0,0 +1,10 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::run_from_file;
+
+	#[test]
+	fn string_interning() {
+		assert_eq!(run_from_file("src/tests/string/interning.lox"), ["true", "true", "true", "true", "matched"]);
+	}
+
+}
\ No newline at end of file
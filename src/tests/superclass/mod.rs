@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::{run_from_file, run_from_file_on_tree};
+
+	#[test]
+	fn superclass_bound_method() {
+		assert_eq!(run_from_file("src/tests/superclass/bound_method.lox"), ["A.method(arg)"]);
+		assert_eq!(run_from_file_on_tree("src/tests/superclass/bound_method.lox"), ["A.method(arg)"]);
+	}
+
+	#[test]
+	fn superclass_call_other_method() {
+		assert_eq!(run_from_file("src/tests/superclass/call_other_method.lox"), ["Derived.bar()", "Base.foo()"]);
+		assert_eq!(run_from_file_on_tree("src/tests/superclass/call_other_method.lox"), ["Derived.bar()", "Base.foo()"]);
+	}
+
+	#[test]
+	fn superclass_call_same_method() {
+		assert_eq!(run_from_file("src/tests/superclass/call_same_method.lox"), ["Derived.foo()", "Base.foo()"]);
+		assert_eq!(run_from_file_on_tree("src/tests/superclass/call_same_method.lox"), ["Derived.foo()", "Base.foo()"]);
+	}
+
+	#[test]
+	fn superclass_closure() {
+		assert_eq!(run_from_file("src/tests/superclass/closure.lox"), ["Base"]);
+		assert_eq!(run_from_file_on_tree("src/tests/superclass/closure.lox"), ["Base"]);
+	}
+
+	#[test]
+	fn superclass_constructor() {
+		assert_eq!(run_from_file("src/tests/superclass/constructor.lox"), ["Derived.init()", "Base.init(a, b)"]);
+		assert_eq!(run_from_file_on_tree("src/tests/superclass/constructor.lox"), ["Derived.init()", "Base.init(a, b)"]);
+	}
+
+	#[test]
+	fn superclass_extra_arguments() {
+		assert_eq!(run_from_file("src/tests/superclass/extra_arguments.lox"), ["[Line 10] Runtime Function Error", "Error Message: Expected 2 arguments but got 4."]);
+	}
+
+	#[test]
+	fn superclass_indirectly_inherited() {
+		assert_eq!(run_from_file("src/tests/superclass/indirectly_inherited.lox"), ["C.foo()", "A.foo()"]);
+		assert_eq!(run_from_file_on_tree("src/tests/superclass/indirectly_inherited.lox"), ["C.foo()", "A.foo()"]);
+	}
+
+	#[test]
+	fn superclass_missing_arguments() {
+		assert_eq!(run_from_file("src/tests/superclass/missing_arguments.lox"), ["[Line 9] Runtime Function Error", "Error Message: Expected 2 arguments but got 1."]);
+	}
+
+	#[test]
+	fn superclass_no_superclass_bind() {
+		assert_eq!(run_from_file("src/tests/superclass/no_superclass_bind.lox"), ["[Line 3] Error at 'super'", "Error Message: Can't use 'super' in a class with no superclass."]);
+	}
+
+	#[test]
+	fn superclass_no_superclass_call() {
+		assert_eq!(run_from_file("src/tests/superclass/no_superclass_call.lox"), ["[Line 3] Error at 'super'", "Error Message: Can't use 'super' in a class with no superclass."]);
+	}
+
+	#[test]
+	fn superclass_no_superclass_method() {
+		assert_eq!(run_from_file("src/tests/superclass/no_superclass_method.lox"), ["[Line 5] Runtime Var Error", "Error Message: Undefined property 'doesNotExist'."]);
+	}
+
+	#[test]
+	fn superclass_parenthesized() {
+		assert_eq!(run_from_file("src/tests/superclass/parenthesized.lox"), ["[Line 7] Error at ')'", "Error Message: Expect '.' after 'super'."]);
+	}
+
+	#[test]
+	fn superclass_reassign_superclass() {
+		assert_eq!(run_from_file("src/tests/superclass/reassign_superclass.lox"), ["Base.method()", "Base.method()"]);
+		assert_eq!(run_from_file_on_tree("src/tests/superclass/reassign_superclass.lox"), ["Base.method()", "Base.method()"]);
+	}
+
+	#[test]
+	fn superclass_super_at_top_level() {
+		assert_eq!(run_from_file("src/tests/superclass/super_at_top_level.lox"), ["[Line 1] Error at 'super'", "Error Message: Can't use 'super' outside of a class.", "[Line 2] Error at 'super'", "Error Message: Can't use 'super' outside of a class."]);
+	}
+
+	#[test]
+	fn superclass_super_in_closure_in_inherited_method() {
+		assert_eq!(run_from_file("src/tests/superclass/super_in_closure_in_inherited_method.lox"), ["A"]);
+		assert_eq!(run_from_file_on_tree("src/tests/superclass/super_in_closure_in_inherited_method.lox"), ["A"]);
+	}
+
+	#[test]
+	fn superclass_super_in_inherited_method() {
+		assert_eq!(run_from_file("src/tests/superclass/super_in_inherited_method.lox"), ["A"]);
+		assert_eq!(run_from_file_on_tree("src/tests/superclass/super_in_inherited_method.lox"), ["A"]);
+	}
+
+	#[test]
+	fn superclass_super_in_top_level_function() {
+		assert_eq!(run_from_file("src/tests/superclass/super_in_top_level_function.lox"), ["[Line 1] Error at 'super'", "Error Message: Can't use 'super' outside of a class."]);
+	}
+
+	#[test]
+	fn superclass_super_without_dot() {
+		assert_eq!(run_from_file("src/tests/superclass/super_without_dot.lox"), ["[Line 5] Error at ';'", "Error Message: Expect '.' after 'super'."]);
+	}
+
+	#[test]
+	fn superclass_super_without_name() {
+		assert_eq!(run_from_file("src/tests/superclass/super_without_name.lox"), ["[Line 5] Error at ';'", "Error Message: Expect superclass method name."]);
+	}
+
+	#[test]
+	fn superclass_this_in_superclass_method() {
+		assert_eq!(run_from_file("src/tests/superclass/this_in_superclass_method.lox"), ["a", "b"]);
+		assert_eq!(run_from_file_on_tree("src/tests/superclass/this_in_superclass_method.lox"), ["a", "b"]);
+	}
+
+}
\ No newline at end of file
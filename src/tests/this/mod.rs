@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+	use crate::tests::{run_from_file, run_from_file_on_tree};
+
+	#[test]
+	fn this_bound_to_receiver() {
+		assert_eq!(run_from_file("src/tests/this/bound_to_receiver.lox"), ["Hi alice", "false"]);
+		assert_eq!(run_from_file_on_tree("src/tests/this/bound_to_receiver.lox"), ["Hi alice", "false"]);
+	}
+
+	#[test]
+	fn this_closure() {
+		assert_eq!(run_from_file("src/tests/this/closure.lox"), ["Foo"]);
+		assert_eq!(run_from_file_on_tree("src/tests/this/closure.lox"), ["Foo"]);
+	}
+
+	#[test]
+	fn this_live_instance() {
+		assert_eq!(run_from_file("src/tests/this/live_instance.lox"), ["2", "11"]);
+		assert_eq!(run_from_file_on_tree("src/tests/this/live_instance.lox"), ["2", "11"]);
+	}
+
+	#[test]
+	fn this_nested_class() {
+		assert_eq!(run_from_file("src/tests/this/nested_class.lox"), ["Outer instance", "Outer instance", "Inner instance"]);
+	}
+
+	#[test]
+	fn this_nested_closure() {
+		assert_eq!(run_from_file("src/tests/this/nested_closure.lox"), ["Foo"]);
+		assert_eq!(run_from_file_on_tree("src/tests/this/nested_closure.lox"), ["Foo"]);
+	}
+
+	#[test]
+	fn this_this_at_top_level() {
+		assert_eq!(run_from_file("src/tests/this/this_at_top_level.lox"), ["[Line 1] Error at 'this'", "Error Message: Can't use 'this' outside of a class."]);
+	}
+
+	#[test]
+	fn this_this_in_method() {
+		assert_eq!(run_from_file("src/tests/this/this_in_method.lox"), ["baz"]);
+		assert_eq!(run_from_file_on_tree("src/tests/this/this_in_method.lox"), ["baz"]);
+	}
+
+	#[test]
+	fn this_this_in_top_level_function() {
+		assert_eq!(run_from_file("src/tests/this/this_in_top_level_function.lox"), ["[Line 2] Error at 'this'", "Error Message: Can't use 'this' outside of a class."]);
+	}
+
+}
\ No newline at end of file
@@ -2,6 +2,21 @@
 mod tests {
 	use crate::tests::run_from_file;
 
+	#[test]
+	fn while_loop_break() {
+		assert_eq!(run_from_file("src/tests/while_loop/break.lox"), ["0", "2", "4", "after"]);
+	}
+
+	#[test]
+	fn while_loop_break_in_function_in_loop() {
+		assert_eq!(run_from_file("src/tests/while_loop/break_in_function_in_loop.lox"), ["[Line 3] Error at 'continue'", "Error Message: Continue statements only allowed in 'for' or 'while' loops."]);
+	}
+
+	#[test]
+	fn while_loop_break_outside_loop() {
+		assert_eq!(run_from_file("src/tests/while_loop/break_outside_loop.lox"), ["[Line 1] Error at 'break'", "Error Message: Break statements only allowed in 'for' or 'while' loops."]);
+	}
+
 	#[test]
 	fn while_loop_class_in_body() {
 		assert_eq!(run_from_file("src/tests/while_loop/class_in_body.lox"), ["[Line 1] Error at 'class'", "Error Message: Expect expression."]);
@@ -12,11 +27,21 @@ mod tests {
 		assert_eq!(run_from_file("src/tests/while_loop/closure_in_body.lox"), ["1", "2", "3"]);
 	}
 
+	#[test]
+	fn while_loop_continue() {
+		assert_eq!(run_from_file("src/tests/while_loop/continue.lox"), ["0", "2", "4", "before", "after"]);
+	}
+
 	#[test]
 	fn while_loop_fun_in_body() {
 		assert_eq!(run_from_file("src/tests/while_loop/fun_in_body.lox"), ["[Line 1] Error at 'fun'", "Error Message: Expect expression."]);
 	}
 
+	#[test]
+	fn while_loop_nested_break() {
+		assert_eq!(run_from_file("src/tests/while_loop/nested_break.lox"), ["outer", "0", "0", "1", "outer"]);
+	}
+
 	#[test]
 	fn while_loop_return_closure() {
 		assert_eq!(run_from_file("src/tests/while_loop/return_closure.lox"), ["i"]);
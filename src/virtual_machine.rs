@@ -1,121 +1,618 @@
-use std::{collections::HashMap, default, env::VarError};
+use std::{collections::HashMap, default, env::VarError, fmt, rc::Rc};
 
-use crate::{bytecode::{Chunk, OpCode, Value}, compiler::{compile, CompilerOutput, Function}, debug::{disassemble_chunk, disassemble_instruction}};
+use crate::{bytecode::{BoundMethod, Chunk, Class, Closure, Instance, OpCode, Upvalue, Value}, compiler::{compile, compile_repl, CompilerOutput}, diagnostics::Diagnostic, debug::{disassemble_chunk, disassemble_instruction}, heap::{Heap, ObjRef, Object}, native::{stdlib, type_name, Arity, NativeCode, NativeError, NativeFn, NativeFunction, Natives}, output::{OutputSink, StdoutSink}, serialize::deserialize};
+
+pub const FRAMES_MAX: usize = 64;
 
 pub struct VirtualMachine {
     frames: Vec<CallFrame>,
-    frame_count: usize,
-    pub chunk: Chunk,
     pub stack: Stack,
-    globals: HashMap<String, Global>,
-    output: Vec<String>,
+    //Keyed by the interned name so lookups never hash the string contents
+    globals: HashMap<ObjRef, Global>,
+    //Upvalues still pointing at a live stack slot, shared by every closure capturing that slot
+    open_upvalues: Vec<ObjRef>,
+    //Printed values are written here straight away
+    output: Box<dyn OutputSink>,
+    //Public so a host can convert values, anything it allocates must be rooted before the VM runs again
+    pub heap: Heap,
+    //Interned "init" so constructing an instance doesn't have to intern it
+    init_string: ObjRef,
+    //Instructions executed since the machine was created, for benchmarking
+    instruction_count: u64
 }
 
 struct CallFrame {
-    function: Function,
-    fp: usize,
-    slots: Value,
+    closure: ObjRef,
+    //The code of the closure's function, kept here so the loop doesn't go through the heap
+    chunk: Rc<Chunk>,
+    ip: usize,
+    //Index of the stack slot holding the called function, locals are relative to this
+    slot: usize,
 }
 
 struct Global {
     value: Value,
     is_const: bool
 }
+#[derive(Debug, PartialEq)]
 pub enum InterpretResult {
     InterpretOk,
     InterpretCompilerError,
-    InterpretRuntimeError
+    InterpretRuntimeError,
+    //The script called exit() with this status
+    InterpretExit(i32)
 }
 
 pub struct InterpreterOutput {
-    result: InterpretResult,
-    pub output: Vec<String>
+    pub result: InterpretResult,
+    //Compile or runtime error lines, printed values go to the VM's output sink
+    pub errors: Vec<String>,
+    //The compile errors with their source positions, for rendering with the offending line
+    pub diagnostics: Vec<Diagnostic>,
+    //The calls that were active when a runtime error happened, innermost first
+    pub stack_trace: Vec<TraceFrame>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceFrame {
+    //None for the top level script
+    pub function: Option<String>,
+    //The line executing in this function, for callers the line of the call
+    pub line: usize
 }
 
-enum RuntimeError {
+//Formatted like clox: "[line 12] in fib()" or "[line 20] in script"
+impl fmt::Display for TraceFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.function {
+            Some(name) => write!(f, "[line {}] in {}()", self.line, name),
+            None => write!(f, "[line {}] in script", self.line)
+        }
+    }
+}
+
+pub enum RuntimeError {
     TypeError(String, usize),
-    VarError(String, usize)
+    VarError(String, usize),
+    FunctionError(String, usize),
+    Exit(i32)
 }
 
-pub fn interpret_vm(src: String, debug: bool) -> InterpreterOutput {
+//disassemble prints the compiled chunks before running, trace prints the stack and every instruction as it executes
+pub fn interpret_vm(src: String, disassemble: bool, trace: bool) -> InterpreterOutput {
+    return VirtualMachine::new().interpret_source(src, disassemble, trace);
+}
 
-    match compile(src) {
-        CompilerOutput::Success(function) => {
-            if debug {
-                disassemble_chunk(&function, match function.name {
-                    Some(name) => &name,
-                    None => "<script>"
-                });
-            }
-            let mut vm = VirtualMachine {
-                chunk,
-                stack: Stack::default(),
-                globals: HashMap::new(),
-                output: vec![],
-                frame_count: 0,
-                frames: vec![]
-            };
-            let program = vm.run(false);
-            if let Err(error) = program {
-                let runtime_error_output;
-                match error {
-                    RuntimeError::TypeError(s, l) => runtime_error_output = vec![format!("[Line {l}] Runtime Type Error"), format!("Error Message: {s}")],
-                    RuntimeError::VarError(s, l) => runtime_error_output = vec![format!("[Line {l}] Runtime Var Error"), format!("Error Message: {s}")]
-                }
+//Runs the contents of a .loxc file without parsing any source
+pub fn interpret_bytecode(bytes: &[u8], disassemble: bool, trace: bool) -> InterpreterOutput {
+    return VirtualMachine::new().interpret_bytecode(bytes, disassemble, trace);
+}
+
+impl VirtualMachine {
+
+    pub fn new() -> VirtualMachine {
+        let mut heap = Heap::default();
+        let init_string = heap.intern(format!("init"));
+        let mut vm = VirtualMachine {
+            stack: Stack::default(),
+            globals: HashMap::new(),
+            open_upvalues: vec![],
+            output: Box::new(StdoutSink),
+            frames: vec![],
+            heap,
+            init_string,
+            instruction_count: 0
+        };
+        for (name, arity, function) in stdlib() {
+            vm.define_function(name, arity, NativeCode::Stdlib(function));
+        }
+        return vm;
+    }
+
+    //Makes a Rust function callable from scripts as a global
+    pub fn define_native(&mut self, name: &str, arity: Arity, function: NativeFn) {
+        self.define_function(name, arity, NativeCode::Host(function));
+    }
+
+    fn define_function(&mut self, name: &str, arity: Arity, function: NativeCode) {
+        let native = self.heap.alloc(Object::Native(NativeFunction { name: format!("{}", name), arity, function }));
+        let name = self.heap.intern(format!("{}", name));
+        self.globals.insert(name, Global { value: Value::Native(native), is_const: false });
+    }
+
+    //Globals are kept between calls so a REPL can run one input after another on the same machine
+    pub fn interpret_source(&mut self, src: String, disassemble: bool, trace: bool) -> InterpreterOutput {
+        let compiled = compile(src, &mut self.heap);
+        return self.interpret_compiled(compiled, disassemble, trace);
+    }
+
+    pub fn interpret_bytecode(&mut self, bytes: &[u8], disassemble: bool, trace: bool) -> InterpreterOutput {
+        match deserialize(bytes, &mut self.heap) {
+            Ok(function) => self.interpret(function, disassemble, trace),
+            Err(error) => {
                 return InterpreterOutput {
-                    result: InterpretResult::InterpretRuntimeError,
-                    output: runtime_error_output
+                    result: InterpretResult::InterpretCompilerError,
+                    errors: vec![format!("Error loading bytecode: {}", error.message())],
+                    diagnostics: vec![],
+                    stack_trace: vec![]
                 }
-            } else {
+            }
+        }
+    }
+
+    //Same as interpret_source but top level expression statements print their value
+    pub fn interpret_repl(&mut self, src: String, disassemble: bool, trace: bool) -> InterpreterOutput {
+        let compiled = compile_repl(src, &mut self.heap);
+        return self.interpret_compiled(compiled, disassemble, trace);
+    }
+
+    fn interpret_compiled(&mut self, compiled: CompilerOutput, disassemble: bool, trace: bool) -> InterpreterOutput {
+        match compiled {
+            CompilerOutput::Success(function) => self.interpret(function, disassemble, trace),
+            CompilerOutput::Error(diagnostics) => {
                 return InterpreterOutput {
-                    result: InterpretResult::InterpretOk,
-                    output: vm.output
+                    result: InterpretResult::InterpretCompilerError,
+                    errors: diagnostics.iter().flat_map(|diagnostic| diagnostic.summary()).collect(),
+                    diagnostics,
+                    stack_trace: vec![]
                 }
             }
-        },
-        CompilerOutput::Error(error) => {
-            return InterpreterOutput {
-                result: InterpretResult::InterpretCompilerError,
-                output: error
+        }
+    }
+
+    fn interpret(&mut self, function: ObjRef, disassemble: bool, trace: bool) -> InterpreterOutput {
+        if disassemble {
+            disassemble_chunk(&self.heap.function(function).chunk, &self.heap.format(&Value::Function(function)), &self.heap);
+        }
+        //The function stays on the stack while its closure is allocated so a collection can't free it
+        self.stack.push(Value::Function(function));
+        let script = self.alloc(Object::Closure(Closure { function, upvalues: vec![] }));
+        self.stack.pop();
+        self.stack.push(Value::Closure(script));
+        let program = self.call(script, 0).and_then(|_| self.run(trace));
+        if let Err(error) = program {
+            return self.runtime_error(error);
+        }
+        //The script's return value
+        self.stack.pop();
+        return InterpreterOutput {
+            result: InterpretResult::InterpretOk,
+            errors: vec![],
+            diagnostics: vec![],
+            stack_trace: vec![]
+        }
+    }
+
+    //Calls a function, class or native from Rust and returns its result, used by the embedding API
+    pub fn call_function(&mut self, callee: Value, args: &[Value]) -> Result<Value, InterpreterOutput> {
+        self.stack.push(callee);
+        for arg in args {
+            self.stack.push(*arg);
+        }
+        //Natives and classes without an initializer finish without pushing a frame
+        let call = self.call_value(callee, args.len()).and_then(|_| if self.frames.is_empty() { Ok(()) } else { self.run(false) });
+        match call {
+            Ok(()) => Ok(self.stack.pop()),
+            Err(error) => Err(self.runtime_error(error))
+        }
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        let name = self.heap.find_interned(name)?;
+        return self.globals.get(&name).map(|global| global.value);
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        let name = self.heap.intern(format!("{}", name));
+        self.globals.insert(name, Global { value, is_const: false });
+    }
+
+    //Sends everything printed from now on to the given sink
+    pub fn set_output(&mut self, output: Box<dyn OutputSink>) {
+        self.output = output;
+    }
+
+    pub fn instruction_count(&self) -> u64 {
+        return self.instruction_count;
+    }
+
+    //Unwinds whatever was executing so the globals can still be used by the next script
+    fn runtime_error(&mut self, error: RuntimeError) -> InterpreterOutput {
+        let stack_trace = self.stack_trace();
+        //Closures stored in globals can outlive the error, so they get their own copy of the values they captured
+        self.close_upvalues(0);
+        self.stack = Stack::default();
+        self.frames.clear();
+        //A host calling a function with the wrong arguments fails before any script code runs, so there's no line to report
+        let location = |line: usize| if line == 0 { format!("") } else { format!("[Line {line}] ") };
+        let runtime_error_output;
+        match error {
+            RuntimeError::TypeError(s, l) => runtime_error_output = vec![format!("{}Runtime Type Error", location(l)), format!("Error Message: {s}")],
+            RuntimeError::VarError(s, l) => runtime_error_output = vec![format!("{}Runtime Var Error", location(l)), format!("Error Message: {s}")],
+            RuntimeError::FunctionError(s, l) => runtime_error_output = vec![format!("{}Runtime Function Error", location(l)), format!("Error Message: {s}")],
+            RuntimeError::Exit(code) => return InterpreterOutput {
+                result: InterpretResult::InterpretExit(code),
+                errors: vec![],
+                diagnostics: vec![],
+                stack_trace: vec![]
             }
         }
-    }         
-} 
-    
+        return InterpreterOutput {
+            result: InterpretResult::InterpretRuntimeError,
+            errors: runtime_error_output,
+            diagnostics: vec![],
+            stack_trace
+        }
+    }
 
+    fn stack_trace(&self) -> Vec<TraceFrame> {
+        return self.frames.iter().rev().map(|frame| {
+            let function = self.heap.function(self.heap.closure(frame.closure).function);
+            TraceFrame { function: function.name.clone(), line: frame.chunk.line(frame.ip.saturating_sub(1)) }
+        }).collect();
+    }
 
-impl VirtualMachine {
+    fn frame(&mut self) -> &mut CallFrame {
+        return self.frames.last_mut().unwrap();
+    }
 
-    fn run(&mut self, execution_tracing: bool) -> Result<(), RuntimeError> {
-        let constants = &self.chunk.constant.clone();
-        loop {
-            
-            let byte = &self.chunk.code[self.ip];
-            self.ip += 1;
+    //Source line of the instruction currently executing, only looked up when it is needed for an error
+    fn current_line(&self) -> usize {
+        match self.frames.last() {
+            Some(frame) => frame.chunk.line(frame.ip - 1),
+            None => 0
+        }
+    }
 
+    fn read_byte(&mut self) -> u8 {
+        let frame = self.frame();
+        let byte = frame.chunk.code[frame.ip];
+        frame.ip += 1;
+        return byte;
+    }
 
-            let (op_code, line_number) = byte;
+    fn read_short(&mut self) -> usize {
+        let frame = self.frame();
+        let short = frame.chunk.read_short(frame.ip);
+        frame.ip += 2;
+        return short;
+    }
+
+    fn read_long(&mut self) -> usize {
+        let frame = self.frame();
+        let long = frame.chunk.read_long(frame.ip);
+        frame.ip += 3;
+        return long;
+    }
+
+    //Names are always stored as string constants by the compiler
+    fn read_name(&mut self) -> ObjRef {
+        let index = self.read_short();
+        match self.frames.last().unwrap().chunk.constant[index] {
+            Value::String(name) => name,
+            //TODO: Add better error handling
+            _ => panic!("Cant find name")
+        }
+    }
+
+    fn alloc(&mut self, object: Object) -> ObjRef {
+        if self.heap.should_collect() {
+            self.collect_garbage();
+        }
+        return self.heap.alloc(object);
+    }
+
+    fn intern(&mut self, string: String) -> ObjRef {
+        if self.heap.should_collect() {
+            self.collect_garbage();
+        }
+        return self.heap.intern(string);
+    }
+
+    //Everything the running program can still reach starts from the stack, globals, frames and open upvalues
+    fn collect_garbage(&mut self) {
+        let mut roots: Vec<ObjRef> = self.stack.values().iter().filter_map(Value::as_object).collect();
+        roots.extend(self.globals.keys());
+        roots.extend(self.globals.values().filter_map(|global| global.value.as_object()));
+        roots.extend(self.frames.iter().map(|frame| frame.closure));
+        roots.extend(self.open_upvalues.iter());
+        roots.push(self.init_string);
+        self.heap.collect_garbage(roots);
+    }
+
+    fn call_value(&mut self, callee: Value, arg_count: usize) -> Result<(), RuntimeError> {
+        match callee {
+            Value::Closure(closure) => self.call(closure, arg_count),
+            Value::Native(native) => self.call_native(native, arg_count),
+            Value::BoundMethod(bound_method) => {
+                let slot = self.stack.len() - arg_count - 1;
+                let BoundMethod { receiver, method } = *self.heap.bound_method(bound_method);
+                self.stack.set(&slot, receiver);
+                self.call(method, arg_count)
+            },
+            Value::Class(class) => {
+                let slot = self.stack.len() - arg_count - 1;
+                let instance = self.alloc(Object::Instance(Instance { class, fields: HashMap::new() }));
+                self.stack.set(&slot, Value::Instance(instance));
+
+                let initializer = self.heap.class(class).methods.get(&self.init_string).copied();
+                if let Some(initializer) = initializer {
+                    return self.call(initializer, arg_count);
+                } else if arg_count != 0 {
+                    return Err(RuntimeError::FunctionError(format!("Expected 0 arguments but got {}.", arg_count), self.current_line()));
+                }
+                Ok(())
+            },
+            _ => Err(RuntimeError::TypeError(format!("Can only call functions and classes."), self.current_line()))
+        }
+    }
+
+    //Natives run straight away, their result replaces the callee and arguments on the stack
+    fn call_native(&mut self, native: ObjRef, arg_count: usize) -> Result<(), RuntimeError> {
+        let NativeFunction { arity, function, .. } = *self.heap.native(native);
+        if let Err(message) = arity.check(arg_count) {
+            return Err(RuntimeError::FunctionError(message, self.current_line()));
+        }
+
+        let slot = self.stack.len() - arg_count - 1;
+        let args = self.stack.values()[slot + 1..].to_vec();
+        let result = match function {
+            NativeCode::Stdlib(function) => function(self, &args).map_err(|error| match error {
+                NativeError::Message(message) => RuntimeError::FunctionError(message, 0),
+                NativeError::Exit(code) => RuntimeError::Exit(code)
+            }),
+            NativeCode::Host(function) => function(&mut self.heap, &args)
+        };
+        let result = match result {
+            Ok(result) => result,
+            Err(RuntimeError::FunctionError(message, _)) => return Err(RuntimeError::FunctionError(message, self.current_line())),
+            Err(error) => return Err(error)
+        };
+        self.stack.truncate(slot);
+        self.stack.push(result);
+        Ok(())
+    }
+
+    fn call(&mut self, closure: ObjRef, arg_count: usize) -> Result<(), RuntimeError> {
+        let function = self.heap.function(self.heap.closure(closure).function);
+        let arity = function.arity;
+        let chunk = function.chunk.clone();
+        if arg_count != arity {
+            return Err(RuntimeError::FunctionError(format!("Expected {} arguments but got {}.", arity, arg_count), self.current_line()));
+        }
+        if self.frames.len() == FRAMES_MAX {
+            return Err(RuntimeError::FunctionError(format!("Stack overflow."), self.current_line()));
+        }
+
+        let slot = self.stack.len() - arg_count - 1;
+        self.frames.push(CallFrame { closure, chunk, ip: 0, slot });
+        Ok(())
+    }
+
+    fn invoke(&mut self, name: ObjRef, arg_count: usize) -> Result<(), RuntimeError> {
+        let instance = match self.stack.peek_at(arg_count) {
+            Value::Instance(instance) => instance,
+            _ => return Err(RuntimeError::TypeError(format!("Only instances have methods."), self.current_line()))
+        };
+
+        //A field holding a function shadows a method of the same name
+        let field = self.heap.instance(instance).fields.get(&name).copied();
+        if let Some(field) = field {
+            let slot = self.stack.len() - arg_count - 1;
+            self.stack.set(&slot, field);
+            return self.call_value(field, arg_count);
+        }
+
+        let class = self.heap.instance(instance).class;
+        return self.invoke_from_class(class, name, arg_count);
+    }
+
+    fn invoke_from_class(&mut self, class: ObjRef, name: ObjRef, arg_count: usize) -> Result<(), RuntimeError> {
+        let method = self.heap.class(class).methods.get(&name).copied();
+        match method {
+            Some(method) => self.call(method, arg_count),
+            None => Err(RuntimeError::VarError(format!("Undefined property '{}'.", self.heap.string(name)), self.current_line()))
+        }
+    }
+
+    //Replaces the receiver on top of the stack with the named method bound to it
+    fn bind_method(&mut self, class: ObjRef, name: ObjRef) -> Result<(), RuntimeError> {
+        let method = self.heap.class(class).methods.get(&name).copied();
+        match method {
+            Some(method) => {
+                //The receiver stays on the stack until the bound method is allocated
+                let receiver = self.stack.peek();
+                let bound_method = self.alloc(Object::BoundMethod(BoundMethod { receiver, method }));
+                self.stack.pop();
+                self.stack.push(Value::BoundMethod(bound_method));
+                Ok(())
+            },
+            None => Err(RuntimeError::VarError(format!("Undefined property '{}'.", self.heap.string(name)), self.current_line()))
+        }
+    }
+
+    fn capture_upvalue(&mut self, slot: usize) -> ObjRef {
+        for upvalue in self.open_upvalues.iter() {
+            if let Upvalue::Open(open_slot) = self.heap.upvalue(*upvalue) {
+                if *open_slot == slot {
+                    return *upvalue;
+                }
+            }
+        }
+
+        let upvalue = self.alloc(Object::Upvalue(Upvalue::Open(slot)));
+        self.open_upvalues.push(upvalue);
+        return upvalue;
+    }
+
+    //Moves every captured variable at or above the given slot off the stack and into its upvalue
+    fn close_upvalues(&mut self, last: usize) {
+        let stack = &self.stack;
+        let heap = &mut self.heap;
+        self.open_upvalues.retain(|upvalue| {
+            let slot = match heap.upvalue(*upvalue) {
+                Upvalue::Open(slot) => *slot,
+                Upvalue::Closed(_) => return false
+            };
+            if slot >= last {
+                *heap.upvalue_mut(*upvalue) = Upvalue::Closed(stack.get(&slot).unwrap());
+                return false;
+            }
+            return true;
+        });
+    }
+
+    fn run(&mut self, execution_tracing: bool) -> Result<(), RuntimeError> {
+        loop {
             if execution_tracing {
-                self.stack.display();
-                disassemble_instruction(op_code, line_number, constants); 
+                let frame = self.frames.last().unwrap();
+                self.stack.display(&self.heap);
+                disassemble_instruction(&frame.chunk, frame.ip, &self.heap);
             }
 
+            let byte = self.read_byte();
+            self.instruction_count += 1;
+            let op_code = match OpCode::from_byte(byte) {
+                Some(op_code) => op_code,
+                //TODO: Add better error handling
+                None => panic!("Unknown opcode {}", byte)
+            };
+            let frame = self.frames.last().unwrap();
+            let closure = frame.closure;
+            let slot = frame.slot;
+
             match op_code {
                 OpCode::Return => {
-                    return Ok(())
-                }, OpCode::Constant(index) => {
-                    if let Some(constant) = constants.get(*index) {
-                        self.stack.push(constant.clone());
+                    let result = self.stack.pop();
+                    self.close_upvalues(slot);
+                    let finished_frame = self.frames.pop().unwrap();
+                    self.stack.truncate(finished_frame.slot);
+                    self.stack.push(result);
+                    //The result of the outermost call is left on the stack for whoever started it
+                    if self.frames.is_empty() {
+                        return Ok(())
+                    }
+                }, OpCode::Call => {
+                    let arg_count = self.read_byte() as usize;
+                    let callee = self.stack.peek_at(arg_count);
+                    self.call_value(callee, arg_count)?;
+                }, OpCode::Closure => {
+                    let index = self.read_short();
+                    if let Value::Function(function) = self.frames.last().unwrap().chunk.constant[index] {
+                        let upvalue_count = self.heap.function(function).upvalue_count;
+                        let mut captured = Vec::with_capacity(upvalue_count);
+                        for _ in 0..upvalue_count {
+                            let is_local = self.read_byte() == 1;
+                            let index = self.read_byte() as usize;
+                            if is_local {
+                                captured.push(self.capture_upvalue(slot + index));
+                            } else {
+                                captured.push(self.heap.closure(closure).upvalues[index]);
+                            }
+                        }
+                        let closure = self.alloc(Object::Closure(Closure { function, upvalues: captured }));
+                        self.stack.push(Value::Closure(closure));
                     } else {
                         //TODO: Add better error handling
-                        panic!("Cant find value")
+                        panic!("Cant find function")
                     }
-                }, 
+                }, OpCode::GetUpvalue => {
+                    let index = self.read_byte() as usize;
+                    let upvalue = self.heap.closure(closure).upvalues[index];
+                    let value = match self.heap.upvalue(upvalue) {
+                        Upvalue::Open(upvalue_slot) => self.stack.get(upvalue_slot).unwrap(),
+                        Upvalue::Closed(value) => *value
+                    };
+                    self.stack.push(value);
+                }, OpCode::SetUpvalue => {
+                    let index = self.read_byte() as usize;
+                    let value = self.stack.peek();
+                    let upvalue = self.heap.closure(closure).upvalues[index];
+                    match self.heap.upvalue_mut(upvalue) {
+                        Upvalue::Open(upvalue_slot) => self.stack.set(upvalue_slot, value),
+                        Upvalue::Closed(closed_value) => *closed_value = value
+                    }
+                }, OpCode::CloseUpvalue => {
+                    let last = self.stack.len() - 1;
+                    self.close_upvalues(last);
+                    self.stack.pop();
+                }, OpCode::Class => {
+                    let name = self.read_name();
+                    let name = self.heap.string(name).clone();
+                    let class = self.alloc(Object::Class(Class { name, methods: HashMap::new() }));
+                    self.stack.push(Value::Class(class));
+                }, OpCode::Method => {
+                    let name = self.read_name();
+                    if let (Value::Closure(method), Value::Class(class)) = (self.stack.peek(), self.stack.peek_at(1)) {
+                        self.heap.class_mut(class).methods.insert(name, method);
+                    }
+                    self.stack.pop();
+                }, OpCode::Inherit => {
+                    let superclass = match self.stack.peek_at(1) {
+                        Value::Class(superclass) => superclass,
+                        _ => return Err(RuntimeError::TypeError(format!("Superclass must be a class."), self.current_line()))
+                    };
+                    if let Value::Class(subclass) = self.stack.peek() {
+                        //Inherited methods are copied down so method lookup never walks the hierarchy
+                        let methods = self.heap.class(superclass).methods.clone();
+                        self.heap.class_mut(subclass).methods.extend(methods);
+                    }
+                    self.stack.pop();
+                }, OpCode::GetProperty => {
+                    let name = self.read_name();
+                    let instance = match self.stack.peek() {
+                        Value::Instance(instance) => instance,
+                        _ => return Err(RuntimeError::TypeError(format!("Only instances have properties."), self.current_line()))
+                    };
+
+                    let field = self.heap.instance(instance).fields.get(&name).copied();
+                    if let Some(value) = field {
+                        self.stack.pop();
+                        self.stack.push(value);
+                    } else {
+                        let class = self.heap.instance(instance).class;
+                        self.bind_method(class, name)?;
+                    }
+                }, OpCode::SetProperty => {
+                    let name = self.read_name();
+                    let instance = match self.stack.peek_at(1) {
+                        Value::Instance(instance) => instance,
+                        _ => return Err(RuntimeError::TypeError(format!("Only instances have fields."), self.current_line()))
+                    };
+
+                    let value = self.stack.pop();
+                    self.heap.instance_mut(instance).fields.insert(name, value);
+                    self.stack.pop();
+                    self.stack.push(value);
+                }, OpCode::Invoke => {
+                    let name = self.read_name();
+                    let arg_count = self.read_byte() as usize;
+                    self.invoke(name, arg_count)?;
+                }, OpCode::GetSuper => {
+                    let name = self.read_name();
+                    if let Value::Class(superclass) = self.stack.pop() {
+                        self.bind_method(superclass, name)?;
+                    }
+                }, OpCode::SuperInvoke => {
+                    let name = self.read_name();
+                    let arg_count = self.read_byte() as usize;
+                    if let Value::Class(superclass) = self.stack.pop() {
+                        self.invoke_from_class(superclass, name, arg_count)?;
+                    }
+                }, OpCode::Constant => {
+                    let index = self.read_byte() as usize;
+                    let constant = self.frames.last().unwrap().chunk.constant[index];
+                    self.stack.push(constant);
+                }, OpCode::ConstantLong => {
+                    let index = self.read_long();
+                    let constant = self.frames.last().unwrap().chunk.constant[index];
+                    self.stack.push(constant);
+                },
                 OpCode::Negate =>  {
                     if let Value::Number(n) = self.stack.pop() {
                         self.stack.push(Value::Number(-n));
                     } else {
-                        return Err(RuntimeError::TypeError(format!("Operand must be a number."), *line_number));
+                        return Err(RuntimeError::TypeError(format!("Operand must be a number."), self.current_line()));
                     }
                     
                 }
@@ -124,8 +621,12 @@ impl VirtualMachine {
                     let n1 = self.stack.pop();
                     match (n1, n2) {
                         (Value::Number(n1), Value::Number(n2)) => self.stack.push(Value::Number(n1 + n2)),
-                        (Value::String(s1), Value::String(s2)) => self.stack.push(Value::String(s1 + &s2)),
-                        _ => return Err(RuntimeError::TypeError(format!("Operand must be either both string or number."), *line_number))
+                        (Value::String(s1), Value::String(s2)) => {
+                            let string = format!("{}{}", self.heap.string(s1), self.heap.string(s2));
+                            let string = self.intern(string);
+                            self.stack.push(Value::String(string));
+                        },
+                        _ => return Err(RuntimeError::TypeError(format!("Operand must be either both string or number."), self.current_line()))
                     }
                 },
                 OpCode::Subtract => {
@@ -133,7 +634,7 @@ impl VirtualMachine {
                     let n1 = self.stack.pop();
                     match (n1, n2) {
                         (Value::Number(n1), Value::Number(n2)) => self.stack.push(Value::Number(n1 - n2)),
-                        _ => return Err(RuntimeError::TypeError(format!("Operand must be both number."), *line_number))
+                        _ => return Err(RuntimeError::TypeError(format!("Operand must be both number."), self.current_line()))
                     }
                 },
                 OpCode::Multiply => {
@@ -141,7 +642,7 @@ impl VirtualMachine {
                     let n1 = self.stack.pop();
                     match (n1, n2) {
                         (Value::Number(n1), Value::Number(n2)) => self.stack.push(Value::Number(n1*n2)),
-                        _ => return Err(RuntimeError::TypeError(format!("Operand must be both number."), *line_number))
+                        _ => return Err(RuntimeError::TypeError(format!("Operand must be both number."), self.current_line()))
                     }
                 },
                 OpCode::Divide => {
@@ -149,14 +650,14 @@ impl VirtualMachine {
                     let n1 = self.stack.pop();
                     match (n1, n2) {
                         (Value::Number(n1), Value::Number(n2)) => self.stack.push(Value::Number(n1/n2)),
-                        _ => return Err(RuntimeError::TypeError(format!("Operand must be both number."), *line_number))
+                        _ => return Err(RuntimeError::TypeError(format!("Operand must be both number."), self.current_line()))
                     }
                 },
                 OpCode::Not => {
                     if let Value::Bool(b) = self.stack.pop() {
                         self.stack.push(Value::Bool(!b));
                     } else {
-                        return Err(RuntimeError::TypeError(format!("Can only negate boolean values."), *line_number))
+                        return Err(RuntimeError::TypeError(format!("Can only negate boolean values."), self.current_line()))
                     }
                 },
                 OpCode::Equal => {
@@ -169,7 +670,7 @@ impl VirtualMachine {
                     let n1 = self.stack.pop();
                     match (n1, n2) {
                         (Value::Number(n1), Value::Number(n2)) => self.stack.push(Value::Bool(n1 > n2)),
-                        _ => return Err(RuntimeError::TypeError(format!("Operand must be both number."), *line_number))
+                        _ => return Err(RuntimeError::TypeError(format!("Operand must be both number."), self.current_line()))
                     }
                 },
                 OpCode::Less => {
@@ -177,103 +678,129 @@ impl VirtualMachine {
                     let n1 = self.stack.pop();
                     match (n1, n2) {
                         (Value::Number(n1), Value::Number(n2)) => self.stack.push(Value::Bool(n1 < n2)),
-                        _ => return Err(RuntimeError::TypeError(format!("Operand must be both number."), *line_number))
+                        _ => return Err(RuntimeError::TypeError(format!("Operand must be both number."), self.current_line()))
                     }
                 }, OpCode::Print => {
                     let v = self.stack.pop();
-                    self.output.push(format!("{v}"));
+                    let line = self.heap.format(&v);
+                    self.output.write_line(&line);
                 }, OpCode::Pop => {
                     self.stack.pop();
                 },
-                OpCode::DefineGlobal(index, is_const) => {
-                    if let Some(Value::String(var_name)) = constants.get(*index) {
-                        let var_value = self.stack.peek();
-                        self.globals.insert(var_name.to_string(), Global {
-                            value: var_value,
-                            is_const: *is_const});
-                        self.stack.pop();
-                    } else {
-                        //TODO: Add better error handling
-                        panic!("Cant find var name")
-                    }
+                OpCode::DefineGlobal => {
+                    let name = self.read_name();
+                    let is_const = self.read_byte() == 1;
+                    let var_value = self.stack.peek();
+                    self.globals.insert(name, Global {
+                        value: var_value,
+                        is_const});
+                    self.stack.pop();
                 },
-                OpCode::GetGlobal(name) => {
-                    if let Some(global) = self.globals.get(name) {
-                        self.stack.push(global.value.clone());
+                OpCode::GetGlobal => {
+                    let name = self.read_name();
+                    if let Some(global) = self.globals.get(&name) {
+                        self.stack.push(global.value);
                     } else {
-                        return Err(RuntimeError::VarError(format!("Undefined variable '{}'.",name), *line_number))
+                        return Err(RuntimeError::VarError(format!("Undefined variable '{}'.", self.heap.string(name)), self.current_line()))
                     }
 
                 },
-                OpCode::SetGlobal(name) => {
-                    if let Some(global) = self.globals.get(name) {
+                OpCode::SetGlobal => {
+                    let name = self.read_name();
+                    if let Some(global) = self.globals.get(&name) {
                         if global.is_const {
-                            return Err(RuntimeError::VarError(format!("Cannot reassign const variable {}",name), *line_number));
+                            return Err(RuntimeError::VarError(format!("Cannot reassign const variable {}", self.heap.string(name)), self.current_line()));
                         }
                         let value = self.stack.peek();
-                        self.globals.insert(name.clone(), Global { value, is_const: global.is_const});
+                        self.globals.insert(name, Global { value, is_const: global.is_const});
                     } else {
-                        return Err(RuntimeError::VarError(format!("Undefined variable '{}'.",name), *line_number))
+                        return Err(RuntimeError::VarError(format!("Undefined variable '{}'.", self.heap.string(name)), self.current_line()))
                     }
                 },
-                OpCode::GetLocal(index) => {
-                    if let Some(value) = self.stack.get(index) {
-                        self.stack.push(value.clone());
+                OpCode::GetLocal => {
+                    let index = self.read_byte() as usize;
+                    if let Some(value) = self.stack.get(&(slot + index)) {
+                        self.stack.push(value);
                     } else {
-                        return Err(RuntimeError::VarError(format!("Undefined variable."), *line_number))
+                        return Err(RuntimeError::VarError(format!("Undefined variable."), self.current_line()))
                     }
                 },
-                OpCode::SetLocal(index) => {
+                OpCode::SetLocal => {
+                    let index = self.read_byte() as usize;
                     let value = self.stack.peek();
-                    self.stack.set(index, value);
+                    self.stack.set(&(slot + index), value);
                     
                 },
-                OpCode::JumpIfFalse(jump_size) => {
+                OpCode::JumpIfFalse => {
+                    let jump_size = self.read_short();
                     match self.stack.peek() {
                         Value::Bool(condition) => if !condition {
-                            self.ip += jump_size;
+                            self.frame().ip += jump_size;
                         },
-                        Value::Nil => self.ip += jump_size,
+                        Value::Nil => self.frame().ip += jump_size,
                         _ => ()
                     }
                 },
-                OpCode::Jump(jump_size) => {
-                    self.ip += jump_size;
+                OpCode::Jump => {
+                    let jump_size = self.read_short();
+                    self.frame().ip += jump_size;
                 }, 
-                OpCode::Loop(jump_back) => {
-                    self.ip -= jump_back;
+                OpCode::Loop => {
+                    let jump_back = self.read_short();
+                    self.frame().ip -= jump_back;
                 },
-                OpCode::SwitchJump(jump_size) => {
+                OpCode::SwitchJump => {
+                    let jump_size = self.read_short();
                     let n1 = self.stack.pop();
                     let n2 = self.stack.peek();
                     if n1 != n2 {
-                        self.ip += jump_size;
+                        self.frame().ip += jump_size;
                     }
-                },
-                // OpCode::Break => {
-                //     loop {
-                //         let (next_opcode, _) = &code[ip];
-                //         self.ip += 1;
-                //         if let OpCode::Loop(_) = next_opcode {
-                //             break;
-                //         }
-                //     }
-                // }, OpCode::Continue => {
-                //     loop {
-                //         let (next_opcode, _) = &code[ip+1];
-                //         if let OpCode::Loop(_) = next_opcode {
-                //             break;
-                //         } 
-                //         self.ip += 1;
-                        
-
-                //     }
-                // }
-                _ => ()
+                }
             }
         }
-        
-        Ok(())
+    }
+}
+
+impl Natives for VirtualMachine {
+    type Value = Value;
+
+    fn nil(&self) -> Value {
+        return Value::Nil;
+    }
+
+    fn number(&self, n: f64) -> Value {
+        return Value::Number(n);
+    }
+
+    fn string(&mut self, string: String) -> Value {
+        return Value::String(self.heap.intern(string));
+    }
+
+    fn as_number(&self, value: &Value) -> Option<f64> {
+        match value {
+            Value::Number(n) => Some(*n),
+            _ => None
+        }
+    }
+
+    fn as_string<'a>(&'a self, value: &'a Value) -> Option<&'a str> {
+        match value {
+            Value::String(string) => Some(self.heap.string(*string)),
+            _ => None
+        }
+    }
+
+    fn format(&self, value: &Value) -> String {
+        return self.heap.format(value);
+    }
+
+    fn type_name(&self, value: &Value) -> &'static str {
+        return type_name(value);
+    }
+
+    fn output(&mut self) -> &mut dyn OutputSink {
+        return self.output.as_mut();
     }
 }
 
@@ -313,14 +840,31 @@ impl Stack {
         return self.stack_vec.push(value);
     }
 
-    pub fn display(&self) {
+    pub fn peek_at(&self, distance: usize) -> Value {
+        return self.stack_vec[self.stack_vec.len() - 1 - distance].clone();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.stack_vec.len();
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.stack_vec.truncate(len);
+    }
+
+    pub fn display(&self, heap: &Heap) {
         print!("          ");
         for value in &self.stack_vec {
-            print!("[{}]",value);
+            print!("[{}]",heap.format(value));
         }
+        print!("\n");
+    }
+
+    pub fn values(&self) -> &Vec<Value> {
+        return &self.stack_vec;
     }
 
-    pub fn get(&mut self, index: &usize) -> Option<Value> {
+    pub fn get(&self, index: &usize) -> Option<Value> {
         return self.stack_vec.get(*index).cloned();
     }
 
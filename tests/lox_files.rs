@@ -0,0 +1,358 @@
+//Runs every .lox file under src/tests and checks it against the expectations written in its comments:
+//
+//print 1; // expect: 1
+//a(); // expect runtime error Type: Can only call functions and classes.
+//print; // Error at ';': Expect expression.
+//
+//The kind of a runtime error is optional, and a compile error can give its line as "// [line 2] Error at ...".
+//Adding a test is just adding a file. Pass part of a path to only run the files matching it:
+//cargo test --test lox_files -- closure
+//Every file runs on both backends, "vm::" or "tree::" only runs the files on one of them.
+use std::{env, fs, path::Path, process};
+
+use lox::{diagnostics::Diagnostic, interpreter::Interpreter, output::OutputBuffer, runner::Backend, virtual_machine::{InterpretResult, InterpreterOutput, VirtualMachine}};
+
+//Files the VM is known to fail, with the reason
+const VM_EXPECTED_FAILURES: &[(&str, &str)] = &[
+    ("limit/no_reuse_constants.lox", "OP_CONSTANT_LONG lifts the 256 constant limit"),
+    ("limit/too_many_constants.lox", "OP_CONSTANT_LONG lifts the 256 constant limit"),
+    ("number/decimal_point_at_eof.lox", "reports the error on the line after the trailing newline"),
+    ("operator/add_bool_nil.lox", "different operand error message"),
+    ("operator/add_bool_num.lox", "different operand error message"),
+    ("operator/add_bool_string.lox", "different operand error message"),
+    ("operator/add_nil_nil.lox", "different operand error message"),
+    ("operator/add_num_nil.lox", "different operand error message"),
+    ("operator/add_string_nil.lox", "different operand error message"),
+    ("operator/divide_nonnum_num.lox", "different operand error message"),
+    ("operator/divide_num_nonnum.lox", "different operand error message"),
+    ("operator/greater_nonnum_num.lox", "different operand error message"),
+    ("operator/greater_num_nonnum.lox", "different operand error message"),
+    ("operator/greater_or_equal_nonnum_num.lox", "different operand error message"),
+    ("operator/greater_or_equal_num_nonnum.lox", "different operand error message"),
+    ("operator/less_nonnum_num.lox", "different operand error message"),
+    ("operator/less_num_nonnum.lox", "different operand error message"),
+    ("operator/less_or_equal_nonnum_num.lox", "different operand error message"),
+    ("operator/less_or_equal_num_nonnum.lox", "different operand error message"),
+    ("operator/multiply_nonnum_num.lox", "different operand error message"),
+    ("operator/multiply_num_nonnum.lox", "different operand error message"),
+    ("operator/not.lox", "'!' only accepts booleans"),
+    ("operator/not_class.lox", "'!' only accepts booleans"),
+    ("operator/subtract_nonnum_num.lox", "different operand error message"),
+    ("operator/subtract_num_nonnum.lox", "different operand error message"),
+    ("string/unterminated.lox", "scanner errors have no trailing period"),
+    ("unexpected_character.lox", "scanner errors have no trailing period"),
+];
+
+//Files the tree-walker is known to fail, with the reason
+const TREE_EXPECTED_FAILURES: &[(&str, &str)] = &[
+    ("class/local_inherit_self.lox", "doesn't report the missing '}' at the end of the file"),
+    ("for_loop/break.lox", "no break or continue"),
+    ("for_loop/break_closure.lox", "no break or continue"),
+    ("for_loop/break_in_switch.lox", "no switch"),
+    ("for_loop/continue.lox", "no break or continue"),
+    ("for_loop/fun_in_body.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("for_loop/nested_continue.lox", "no break or continue"),
+    ("for_loop/statement_condition.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("for_loop/statement_increment.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("for_loop/statement_initializer.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("for_loop/var_in_body.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("function/body_must_be_block.lox", "doesn't report the missing '}' at the end of the file"),
+    ("function/missing_comma_in_parameters.lox", "the parameter error has no trailing period"),
+    ("if_statement/fun_in_else.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("if_statement/fun_in_then.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("if_statement/var_in_else.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("if_statement/var_in_then.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("inheritance/parenthesized_superclass.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("limit/loop_too_large.lox", "no jump offsets to limit"),
+    ("limit/no_reuse_constants.lox", "no constant table to limit"),
+    ("limit/too_many_constants.lox", "no constant table to limit"),
+    ("limit/too_many_locals.lox", "no local slots to limit"),
+    ("limit/too_many_upvalues.lox", "no upvalue slots to limit"),
+    ("number/decimal_point_at_eof.lox", "reports the error on the line after the trailing newline"),
+    ("number/leading_dot.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("operator/add_bool_nil.lox", "different operand error message"),
+    ("operator/add_bool_num.lox", "different operand error message"),
+    ("operator/add_bool_string.lox", "different operand error message"),
+    ("operator/add_nil_nil.lox", "different operand error message"),
+    ("operator/add_num_nil.lox", "different operand error message"),
+    ("operator/add_string_nil.lox", "different operand error message"),
+    ("operator/divide_nonnum_num.lox", "different operand error message"),
+    ("operator/divide_num_nonnum.lox", "different operand error message"),
+    ("operator/greater_nonnum_num.lox", "different operand error message"),
+    ("operator/greater_num_nonnum.lox", "different operand error message"),
+    ("operator/greater_or_equal_nonnum_num.lox", "different operand error message"),
+    ("operator/greater_or_equal_num_nonnum.lox", "different operand error message"),
+    ("operator/less_nonnum_num.lox", "different operand error message"),
+    ("operator/less_num_nonnum.lox", "different operand error message"),
+    ("operator/less_or_equal_nonnum_num.lox", "different operand error message"),
+    ("operator/less_or_equal_num_nonnum.lox", "different operand error message"),
+    ("operator/multiply_nonnum_num.lox", "different operand error message"),
+    ("operator/multiply_num_nonnum.lox", "different operand error message"),
+    ("operator/not.lox", "'!' only accepts booleans"),
+    ("operator/not_class.lox", "'!' only accepts booleans"),
+    ("operator/subtract_nonnum_num.lox", "different operand error message"),
+    ("operator/subtract_num_nonnum.lox", "different operand error message"),
+    ("string/interning.lox", "no switch"),
+    ("string/unterminated.lox", "scanner errors have no trailing period"),
+    ("superclass/parenthesized.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("superclass/super_without_dot.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("superclass/super_without_name.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("unexpected_character.lox", "scanner errors have no trailing period"),
+    ("while_loop/break.lox", "no break or continue"),
+    ("while_loop/break_in_function_in_loop.lox", "no break or continue"),
+    ("while_loop/break_outside_loop.lox", "no break or continue"),
+    ("while_loop/continue.lox", "no break or continue"),
+    ("while_loop/fun_in_body.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+    ("while_loop/nested_break.lox", "no break or continue"),
+    ("while_loop/var_in_body.lox", "error recovery skips one token, so the rest of the statement reports more errors"),
+];
+
+//Directories whose files have no expectations to check
+const NOT_RUN: &[&str] = &[
+    //Only print timings, and take too long in a debug build
+    "benchmark/",
+    //Expect the token dump of the book's Java scanner, which this interpreter doesn't have
+    "scanning/",
+];
+
+#[derive(Default)]
+struct Expectations {
+    output: Vec<String>,
+    compile_errors: Vec<CompileError>,
+    runtime_error: Option<RuntimeError>
+}
+
+#[derive(Debug)]
+struct CompileError {
+    line: usize,
+    //"'x'" or "end", None when the test doesn't say where on the line
+    location: Option<String>,
+    message: String
+}
+
+struct RuntimeError {
+    line: usize,
+    //Var, Type or Function, None when the test only gives the message
+    kind: Option<String>,
+    message: String
+}
+
+fn parse_expectations(source: &str) -> Expectations {
+    let mut expectations = Expectations::default();
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        if let Some(start) = text.find("// expect: ") {
+            expectations.output.push(text[start + "// expect: ".len()..].to_string());
+        } else if let Some(start) = text.find("expect runtime error") {
+            let rest = &text[start + "expect runtime error".len()..];
+            let (kind, message) = match rest.strip_prefix(':') {
+                Some(message) => (None, message),
+                None => match rest.trim_start().split_once(':') {
+                    Some((kind, message)) => (Some(kind.to_string()), message),
+                    None => continue
+                }
+            };
+            expectations.runtime_error = Some(RuntimeError { line, kind, message: message.trim().to_string() });
+        } else if let Some(start) = text.find("//") {
+            parse_compile_errors(&text[start + 2..], line, &mut expectations.compile_errors);
+        }
+    }
+    return expectations;
+}
+
+//A comment can hold several errors: "Error at 'a': Expect expression. Error at 'b': Expect ';' after value."
+fn parse_compile_errors(comment: &str, line: usize, errors: &mut Vec<CompileError>) {
+    let mut comment = comment.trim();
+    let mut line = line;
+    //Errors only the Java implementation reports
+    if comment.starts_with("[java line") {
+        return;
+    }
+    if let Some(rest) = comment.strip_prefix("[line ").or_else(|| comment.strip_prefix("[c line ")) {
+        let Some((number, rest)) = rest.split_once(']') else {
+            return;
+        };
+        line = number.parse().unwrap_or(line);
+        comment = rest.trim_start();
+    }
+    //Only comments that start with an error are expectations, not prose that mentions one
+    if !comment.starts_with("Error") {
+        return;
+    }
+
+    for error in comment.split("Error").skip(1) {
+        let (location, message) = if let Some(message) = error.strip_prefix(": ") {
+            (None, message)
+        } else if let Some(rest) = error.strip_prefix(" at ") {
+            match rest.split_once(": ") {
+                Some((location, message)) => (Some(location.to_string()), message),
+                None => continue
+            }
+        } else {
+            continue;
+        };
+        errors.push(CompileError { line, location, message: message.trim().to_string() });
+    }
+}
+
+fn matches(expected: &CompileError, diagnostic: &Diagnostic) -> bool {
+    let location_matches = match expected.location.as_deref() {
+        None => true,
+        //The scanner names the end of file token EOF
+        Some("end") => diagnostic.lexeme == "EOF",
+        Some(location) => location == format!("'{}'", diagnostic.lexeme)
+    };
+    return expected.line == diagnostic.line && expected.message == diagnostic.message && location_matches;
+}
+
+fn result_name(result: &InterpretResult) -> String {
+    match result {
+        InterpretResult::InterpretOk => format!("ran to completion"),
+        InterpretResult::InterpretCompilerError => format!("failed to compile"),
+        InterpretResult::InterpretRuntimeError => format!("failed at runtime"),
+        InterpretResult::InterpretExit(code) => format!("exited with {}", code)
+    }
+}
+
+//Compares a run with the expectations, describing the first difference
+fn check(expectations: &Expectations, printed: Vec<String>, output: InterpreterOutput) -> Result<(), String> {
+    if !expectations.compile_errors.is_empty() {
+        if output.result != InterpretResult::InterpretCompilerError {
+            return Err(format!("expected compile errors {:?} but the script {}", expectations.compile_errors, result_name(&output.result)));
+        }
+        let missing: Vec<&CompileError> = expectations.compile_errors.iter().filter(|expected| !output.diagnostics.iter().any(|diagnostic| matches(expected, diagnostic))).collect();
+        let unexpected: Vec<String> = output.diagnostics.iter().filter(|diagnostic| !expectations.compile_errors.iter().any(|expected| matches(expected, diagnostic))).flat_map(|diagnostic| diagnostic.summary()).collect();
+        if !missing.is_empty() || !unexpected.is_empty() {
+            return Err(format!("missing compile errors {:?}, unexpected compile errors {:?}", missing, unexpected));
+        }
+        return Ok(());
+    }
+
+    if printed != expectations.output {
+        return Err(format!("expected output {:?} but got {:?}", expectations.output, printed));
+    }
+    match &expectations.runtime_error {
+        Some(expected) => {
+            if output.result != InterpretResult::InterpretRuntimeError {
+                return Err(format!("expected runtime error '{}' but the script {} {:?}", expected.message, result_name(&output.result), output.errors));
+            }
+            let kind = expected.kind.as_deref().map(|kind| format!("{} ", kind)).unwrap_or_default();
+            let header = format!("[Line {}] Runtime {}", expected.line, kind);
+            let message = format!("Error Message: {}", expected.message);
+            if !output.errors.first().is_some_and(|line| line.starts_with(&header)) || output.errors.get(1) != Some(&message) {
+                return Err(format!("expected {:?} but got {:?}", [format!("{}Error", header), message], output.errors));
+            }
+        },
+        None => {
+            if !matches!(output.result, InterpretResult::InterpretOk | InterpretResult::InterpretExit(_)) {
+                return Err(format!("expected the script to run but it {} {:?}", result_name(&output.result), output.errors));
+            }
+        }
+    }
+    return Ok(());
+}
+
+fn run_file(path: &Path, backend: Backend) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|error| format!("can't read the file: {}", error))?;
+    let expectations = parse_expectations(&source);
+    let buffer = OutputBuffer::default();
+    let output = match backend {
+        Backend::Vm => {
+            let mut vm = VirtualMachine::new();
+            vm.set_output(Box::new(buffer.clone()));
+            vm.interpret_source(source, false, false)
+        },
+        Backend::Tree => {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_output(Box::new(buffer.clone()));
+            interpreter.interpret_source(source)
+        }
+    };
+    //A printed string can span several lines
+    let printed = buffer.take().iter().flat_map(|line| line.split('\n').map(|line| line.to_string()).collect::<Vec<_>>()).collect();
+    return check(&expectations, printed, output);
+}
+
+//Every .lox file under dir, as paths relative to root
+fn lox_files(root: &Path, dir: &Path, files: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).expect("Error: can't read test directory") {
+        let path = entry.expect("Error: can't read test directory").path();
+        if path.is_dir() {
+            lox_files(root, &path, files);
+        } else if path.extension().is_some_and(|extension| extension == "lox") {
+            files.push(path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"));
+        }
+    }
+}
+
+//Counts for one backend, so conformance can be followed separately for each
+#[derive(Default)]
+struct Summary {
+    passed: usize,
+    failed: usize,
+    expected_failures: usize
+}
+
+fn run_backend(backend: Backend, root: &Path, files: &[String], filter: Option<&str>, failures: &mut Vec<String>) -> Summary {
+    let (prefix, expected_failures) = match backend {
+        Backend::Vm => ("vm", VM_EXPECTED_FAILURES),
+        Backend::Tree => ("tree", TREE_EXPECTED_FAILURES)
+    };
+    let mut summary = Summary::default();
+    for file in files.iter() {
+        let name = format!("{}::{}", prefix, file);
+        if NOT_RUN.iter().any(|dir| file.starts_with(dir)) || filter.is_some_and(|filter| !name.contains(filter)) {
+            continue;
+        }
+        let expected_failure = expected_failures.iter().find(|(expected, _)| expected == file);
+        match (run_file(&root.join(file), backend), expected_failure) {
+            (Ok(()), None) => {
+                println!("test {} ... ok", name);
+                summary.passed += 1;
+            },
+            (Ok(()), Some(_)) => {
+                println!("test {} ... FAILED", name);
+                failures.push(format!("{}: passes now, remove it from the {} expected failures", name, prefix));
+                summary.failed += 1;
+            },
+            (Err(_), Some((_, reason))) => {
+                println!("test {} ... expected failure ({})", name, reason);
+                summary.expected_failures += 1;
+            },
+            (Err(error), None) => {
+                println!("test {} ... FAILED", name);
+                failures.push(format!("{}: {}", name, error));
+                summary.failed += 1;
+            }
+        }
+    }
+    return summary;
+}
+
+fn main() {
+    let filter = env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+    let mut files = vec![];
+    lox_files(&root, &root, &mut files);
+    files.sort();
+
+    let mut failures = vec![];
+    let vm = run_backend(Backend::Vm, &root, &files, filter.as_deref(), &mut failures);
+    let tree = run_backend(Backend::Tree, &root, &files, filter.as_deref(), &mut failures);
+
+    if !failures.is_empty() {
+        println!("\nfailures:");
+        for failure in failures.iter() {
+            println!("    {}", failure);
+        }
+    }
+    println!();
+    for (name, summary) in [("vm", &vm), ("tree", &tree)] {
+        println!("{}: {} passed; {} failed; {} expected failures", name, summary.passed, summary.failed, summary.expected_failures);
+    }
+    let status = if failures.is_empty() { "ok" } else { "FAILED" };
+    println!("\ntest result: {}. {} passed; {} failed; {} expected failures\n", status, vm.passed + tree.passed, failures.len(), vm.expected_failures + tree.expected_failures);
+    if !failures.is_empty() {
+        process::exit(1);
+    }
+}